@@ -0,0 +1,284 @@
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{Data, DeriveInput};
+
+use crate::{add_trait_bound, dump_expansion, dump_requested, TypeParamVisitor};
+
+pub(crate) fn derive_custom_display(ast: &DeriveInput) -> Result<proc_macro2::TokenStream, syn::Error> {
+    let ident = &ast.ident;
+
+    // One `(pattern path, fields, template)` per match arm.
+    let mut arms = vec![];
+    match &ast.data {
+        Data::Struct(ds) => {
+            let lit = display_attr(&ast.attrs)?
+                .ok_or_else(|| syn::Error::new_spanned(ident, "expected `#[display(\"...\")]`"))?;
+            arms.push((quote!(Self), &ds.fields, Template::parse(&lit, &ds.fields)?));
+        }
+        Data::Enum(de) => {
+            if let Some(lit) = display_attr(&ast.attrs)? {
+                return Err(syn::Error::new_spanned(lit, "the template of an enum goes on each variant, not on the enum"));
+            }
+            for variant in &de.variants {
+                let var_ident = &variant.ident;
+                let template = match display_attr(&variant.attrs)? {
+                    Some(lit) => Template::parse(&lit, &variant.fields)?,
+                    // Unit variants without a template print their name.
+                    None if variant.fields.is_empty() => Template {
+                        lit: syn::LitStr::new(&var_ident.to_string(), var_ident.span()),
+                        args: vec![],
+                    },
+                    None => {
+                        return Err(syn::Error::new_spanned(variant, "expected `#[display(\"...\")]`"));
+                    }
+                };
+                arms.push((quote!(Self::#var_ident), &variant.fields, template));
+            }
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(ident, "CustomDisplay does not support unions"));
+        }
+    }
+
+    // Group the referenced field types by the formatting trait they need.
+    let mut bounds: Vec<(syn::Path, Vec<&syn::Type>)> = vec![];
+    for (_, fields, template) in &arms {
+        for arg in &template.args {
+            let ty = member_type(fields, &arg.member);
+            for tr in &arg.traits {
+                let key = quote!(#tr).to_string();
+                match bounds.iter_mut().find(|(b, _)| quote!(#b).to_string() == key) {
+                    Some((_, types)) => types.push(ty),
+                    None => bounds.push((tr.clone(), vec![ty])),
+                }
+            }
+        }
+    }
+    // `{p:p}` formats the field itself, usually a reference or a pointer, so
+    // unlike the other traits `Pointer` is required of the whole field type.
+    let pointer: syn::Path = syn::parse_quote!(std::fmt::Pointer);
+    let pointer_key = quote!(#pointer).to_string();
+    let params = ast.generics.type_params()
+        .map(|t| t.ident.clone())
+        .collect::<Vec<_>>();
+    let visitor = TypeParamVisitor::new(&params, &[]);
+    let mut generics = ast.generics.clone();
+    for (bound, types) in &bounds {
+        if quote!(#bound).to_string() == pointer_key {
+            let where_clause = generics.make_where_clause();
+            for ty in types.iter().filter(|ty| visitor.mentions_param(ty)) {
+                where_clause.predicates.push(syn::parse_quote!(#ty: #bound));
+            }
+        } else {
+            generics = add_trait_bound(generics, types, bound, &[]);
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let arms = arms.iter().map(|(path, _, template)| {
+        let lit = &template.lit;
+        let members = template.args.iter().map(|arg| &arg.member);
+        let locals = template.args.iter().map(|arg| format_ident!("__self{}", arg.name));
+        let names = template.args.iter().map(|arg| format_ident!("{}", arg.name));
+        let values = template.args.iter().map(|arg| format_ident!("__self{}", arg.name));
+        // The bindings are references to the fields. `write!` only borrows
+        // its arguments, so dereferencing them doesn't move anything, and
+        // `{p:p}` prints the field rather than where it is stored.
+        quote! {
+            #path { #(#members: #locals,)* .. } => ::core::write!(f, #lit, #(#names = *#values),*),
+        }
+    }).collect::<Vec<_>>();
+
+    // An enum without variants has no value to match on by reference.
+    let body = if arms.is_empty() {
+        quote! { match *self {} }
+    } else {
+        quote! {
+            match self {
+                #(#arms)*
+            }
+        }
+    };
+
+    let out = quote! {
+        impl #impl_generics std::fmt::Display for #ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                #body
+            }
+        }
+    };
+
+    if dump_requested() {
+        dump_expansion(ast, "display", &out)?;
+    }
+    Ok(out)
+}
+
+fn display_attr(attrs: &[syn::Attribute]) -> Result<Option<syn::LitStr>, syn::Error> {
+    let mut out = None;
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("display")) {
+        match attr.parse_meta()? {
+            syn::Meta::List(list) if list.nested.len() == 1 => match &list.nested[0] {
+                syn::NestedMeta::Lit(syn::Lit::Str(lit)) => out = Some(lit.clone()),
+                nested => return Err(syn::Error::new_spanned(nested, "expected string literal")),
+            },
+            meta => return Err(syn::Error::new_spanned(meta, "expected `display(\"...\")`")),
+        }
+    }
+    Ok(out)
+}
+
+fn member_type<'a>(fields: &'a syn::Fields, member: &syn::Member) -> &'a syn::Type {
+    match member {
+        syn::Member::Named(ident) => &fields.iter().find(|f| f.ident.as_ref() == Some(ident)).unwrap().ty,
+        syn::Member::Unnamed(index) => &fields.iter().nth(index.index as usize).unwrap().ty,
+    }
+}
+
+// A `#[display("...")]` template rewritten so that every field is passed to
+// `write!` as a named argument, e.g. `"{0:>width$}"` becomes `"{_0:>_width$}"`.
+// The leading underscore keeps fields such as `r#type` from turning into a
+// keyword argument.
+struct Template {
+    lit: syn::LitStr,
+    args: Vec<TemplateArg>,
+}
+
+struct TemplateArg {
+    // Name of the argument in the rewritten template.
+    name: String,
+    member: syn::Member,
+    // Formatting traits the field is used with; empty for width and precision.
+    traits: Vec<syn::Path>,
+}
+
+impl Template {
+    fn parse(lit: &syn::LitStr, fields: &syn::Fields) -> Result<Self, syn::Error> {
+        let value = lit.value();
+        let mut out = String::new();
+        let mut args: Vec<TemplateArg> = vec![];
+        let mut chars = value.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    out.push_str("{{");
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    out.push_str("}}");
+                }
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => placeholder.push(c),
+                            None => return Err(syn::Error::new_spanned(lit, "invalid format string: expected `}`")),
+                        }
+                    }
+                    let (name, spec) = match placeholder.split_once(':') {
+                        Some((name, spec)) => (name.trim(), spec),
+                        None => (placeholder.trim(), ""),
+                    };
+                    let name = Self::push_arg(&mut args, lit, fields, name, Some(spec_trait(spec)))?;
+                    let spec = Self::rewrite_spec(&mut args, lit, fields, spec)?;
+                    out.push('{');
+                    out.push_str(&name);
+                    if !spec.is_empty() {
+                        out.push(':');
+                        out.push_str(&spec);
+                    }
+                    out.push('}');
+                }
+                '}' => return Err(syn::Error::new_spanned(lit, "invalid format string: unmatched `}`")),
+                c => out.push(c),
+            }
+        }
+        Ok(Template { lit: syn::LitStr::new(&out, lit.span()), args })
+    }
+
+    // Replace `name$` width and precision arguments in a format spec.
+    fn rewrite_spec(args: &mut Vec<TemplateArg>, lit: &syn::LitStr, fields: &syn::Fields,
+                    spec: &str) -> Result<String, syn::Error> {
+        if spec.contains(".*") {
+            return Err(syn::Error::new_spanned(lit, "`.*` precision is not supported, use `.field$`"));
+        }
+        let mut out = String::new();
+        let mut word = String::new();
+        for c in spec.chars() {
+            if c.is_alphanumeric() || c == '_' {
+                word.push(c);
+                continue;
+            }
+            if c == '$' {
+                word = Self::push_arg(args, lit, fields, &word, None)?;
+            }
+            out.push_str(&word);
+            out.push(c);
+            word.clear();
+        }
+        out.push_str(&word);
+        Ok(out)
+    }
+
+    // Record a reference to the field `name` and return its argument name.
+    fn push_arg(args: &mut Vec<TemplateArg>, lit: &syn::LitStr, fields: &syn::Fields,
+                name: &str, trait_: Option<syn::Path>) -> Result<String, syn::Error> {
+        if name.is_empty() {
+            return Err(syn::Error::new_spanned(lit, "implicit positional arguments are not supported, refer to the field by name or index"));
+        }
+        // `{type}` and `{r#type}` both refer to the field `r#type`.
+        let name = name.strip_prefix("r#").unwrap_or(name);
+        let member = match fields {
+            syn::Fields::Named(_) => fields.iter()
+                .filter_map(|f| f.ident.as_ref())
+                .find(|ident| ident.unraw() == name)
+                .map(|ident| syn::Member::Named(ident.clone())),
+            _ => name.parse::<usize>().ok()
+                .filter(|index| *index < fields.len())
+                .map(|index| syn::Member::Unnamed(syn::Index { index: index as u32, span: lit.span() })),
+        };
+        let member = member.ok_or_else(|| {
+            syn::Error::new_spanned(lit, format!("no field named `{}`", name))
+        })?;
+        let arg_name = match &member {
+            syn::Member::Named(ident) => format!("_{}", ident.unraw()),
+            syn::Member::Unnamed(index) => format!("_{}", index.index),
+        };
+        let arg = match args.iter_mut().find(|arg| arg.name == arg_name) {
+            Some(arg) => arg,
+            None => {
+                args.push(TemplateArg { name: arg_name.clone(), member, traits: vec![] });
+                args.last_mut().unwrap()
+            }
+        };
+        if let Some(trait_) = trait_ {
+            let key = quote!(#trait_).to_string();
+            if !arg.traits.iter().any(|t| quote!(#t).to_string() == key) {
+                arg.traits.push(trait_);
+            }
+        }
+        Ok(arg_name)
+    }
+}
+
+// The formatting trait selected by the type at the end of a format spec.
+fn spec_trait(spec: &str) -> syn::Path {
+    let name = if spec.ends_with('?') {
+        "Debug"
+    } else {
+        match spec.chars().last() {
+            Some('x') => "LowerHex",
+            Some('X') => "UpperHex",
+            Some('o') => "Octal",
+            Some('b') => "Binary",
+            Some('e') => "LowerExp",
+            Some('E') => "UpperExp",
+            Some('p') => "Pointer",
+            _ => "Display",
+        }
+    };
+    let ident = format_ident!("{}", name);
+    syn::parse_quote!(std::fmt::#ident)
+}
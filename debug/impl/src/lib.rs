@@ -0,0 +1,752 @@
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, parse_quote, DeriveInput, Data};
+use syn::ext::IdentExt;
+use syn::visit::Visit;
+use quote::{quote, ToTokens};
+
+mod display;
+
+#[proc_macro_derive(CustomDebug, attributes(debug))]
+pub fn derive(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+
+    let out = match derive_custom_debug(&ast) {
+        Ok(out) => out,
+        Err(e) => e.to_compile_error(),
+    };
+    out.into()
+}
+
+#[proc_macro_derive(CustomDisplay, attributes(display))]
+pub fn derive_display(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+
+    let out = match display::derive_custom_display(&ast) {
+        Ok(out) => out,
+        Err(e) => e.to_compile_error(),
+    };
+    out.into()
+}
+
+fn derive_custom_debug(ast: &DeriveInput) -> Result<proc_macro2::TokenStream, syn::Error> {
+    let ident = &ast.ident;
+    let container = ContainerAttrs::parse(&ast.attrs)?;
+    if container.opaque.is_some() || container.with.is_some() {
+        return derive_opaque_debug(ast, &container);
+    }
+    let fields = match &ast.data {
+        Data::Struct(s) => s.fields.iter()
+            .map(|f| Ok((f, FieldAttrs::parse(&f.attrs)?)))
+            .collect::<Result<Vec<_>, syn::Error>>()?,
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(ident, "unions require `#[debug(opaque = \"...\")]` or `#[debug(with = \"...\")]`"));
+        }
+        _ => {
+            return Err(syn::Error::new_spanned(ident, "Only struct implemented"));
+        }
+    };
+
+    let krate = container.krate();
+    let mut generics = if let Some(escape_hatch) = &container.bound {
+        add_trait_bound_with_escape_hatch(ast.generics.clone(), escape_hatch)?
+    } else {
+        // Fields formatted by a `with` function do not require `Debug`, and fields
+        // with their own `bound` replace whatever would be inferred from them.
+        let inferred = fields.iter()
+            .filter(|(_, attrs)| attrs.with.is_none() && attrs.bound.is_none() && !attrs.flatten)
+            .map(|(f, _)| &f.ty)
+            .collect::<Vec<_>>();
+        let mut generics = add_trait_bound(ast.generics.clone(), &inferred,
+                                           &parse_quote!(std::fmt::Debug), &container.no_bound_for);
+        // A flattened field only has to implement `DebugFields`, which is
+        // required of its whole type so that a bare `T` is bounded correctly too.
+        let params = ast.generics.type_params()
+            .map(|t| t.ident.clone())
+            .collect::<Vec<_>>();
+        let visitor = TypeParamVisitor::new(&params, &[]);
+        let where_clause = generics.make_where_clause();
+        for (f, _) in fields.iter().filter(|(f, attrs)| attrs.flatten && attrs.bound.is_none() && visitor.mentions_param(&f.ty)) {
+            let ty = &f.ty;
+            where_clause.predicates.push(parse_quote!(#ty: #krate::DebugFields));
+        }
+        generics
+    };
+    // Per-field predicates are added on top of the container's, whether those
+    // were inferred or written out.
+    for (_, attrs) in &fields {
+        if let Some(bound) = &attrs.bound {
+            generics = add_trait_bound_with_escape_hatch(generics, bound)?;
+        }
+    }
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // if let Data::Struct(ds) = &ast.data {
+    //     let fields_iter = ds.fields.iter()
+    //         .map(|f| (&f.attrs, f.ident.as_ref().unwrap(), &f.ty));
+
+    //     let _ = fields_iter
+    //         .map(|(attrs, _, _)| fmt_attrs(attrs))
+    //         .collect::<Vec<_>>();
+    // }
+
+    let name = match &container.name {
+        Some(name) => name.value(),
+        None => ident.unraw().to_string(),
+    };
+
+    // `{:#?}` is turned into `{:?}` by formatting again without the flag.
+    let compact = if container.compact {
+        quote! {
+            if f.alternate() {
+                return ::core::write!(f, "{:?}", self);
+            }
+        }
+    } else {
+        proc_macro2::TokenStream::new()
+    };
+
+    let out = if container.transparent {
+        if let Some(fields) = &container.fields {
+            return Err(syn::Error::new_spanned(fields, "a transparent type has no fields of its own to flatten"));
+        }
+        let body = fmt_transparent(ident, &fields)?;
+        quote! {
+            impl #impl_generics std::fmt::Debug for #ident #ty_generics #where_clause {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    #compact
+                    #body
+                }
+            }
+        }
+    } else {
+        if let Some((f, _)) = fields.iter().find(|(f, _)| f.ident.is_none()) {
+            return Err(syn::Error::new_spanned(f, "tuple struct fields are only supported with `#[debug(transparent)]`"));
+        }
+
+        let fields_token = proc_macro2::TokenStream::from_iter(
+            fields.iter().map(|(f, attrs)| fmt_field(f, attrs, container.rename_all, &krate)));
+
+        let helper = if fields.iter().any(|(_, attrs)| attrs.with.is_some()) {
+            debug_with_helper()
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+
+        // Only types that can be flattened into others refer to the
+        // derive_debug crate, the `Debug` impl itself doesn't need it.
+        let debug_fields = if container.fields.is_some() {
+            quote! {
+                impl #impl_generics #krate::DebugFields for #ident #ty_generics #where_clause {
+                    fn debug_fields(&self, s: &mut std::fmt::DebugStruct<'_, '_>) {
+                        #helper
+                        #fields_token
+                    }
+                }
+            }
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+
+        quote! {
+            #debug_fields
+
+            impl #impl_generics std::fmt::Debug for #ident #ty_generics #where_clause {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    #compact
+                    let s = &mut f.debug_struct(#name);
+                    #helper
+                    #fields_token
+                    s.finish()
+                }
+            }
+        }
+    };
+
+    if container.dump || dump_requested() {
+        dump_expansion(ast, "debug", &out)?;
+    }
+    Ok(out)
+}
+
+
+// A type marked `#[debug(opaque = "...")]` or `#[debug(with = "...")]` is never
+// looked into, which is what makes these the only way to derive `Debug` for a
+// union: the opaque form prints the type name, the summary and the size of the
+// type, e.g. `Value(int or float, 8 bytes)`, without reading any field.
+fn derive_opaque_debug(ast: &DeriveInput, container: &ContainerAttrs) -> Result<proc_macro2::TokenStream, syn::Error> {
+    let ident = &ast.ident;
+    let generics = match &container.bound {
+        Some(escape_hatch) => add_trait_bound_with_escape_hatch(ast.generics.clone(), escape_hatch)?,
+        None => ast.generics.clone(),
+    };
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let name = match &container.name {
+        Some(name) => name.value(),
+        None => ident.unraw().to_string(),
+    };
+    if let Some(fields) = &container.fields {
+        return Err(syn::Error::new_spanned(fields, "an opaque type has no fields to flatten"));
+    }
+    let body = match (&container.opaque, &container.with) {
+        (Some(_), Some(with)) => {
+            return Err(syn::Error::new_spanned(with, "`opaque` cannot be combined with `with`"));
+        }
+        (Some(opaque), None) => quote! {
+            f.debug_tuple(#name)
+                .field(&::core::format_args!("{}", #opaque))
+                .field(&::core::format_args!("{} bytes", ::core::mem::size_of::<Self>()))
+                .finish()
+        },
+        (None, Some(with)) => quote! { #with(self, f) },
+        (None, None) => unreachable!(),
+    };
+
+    let out = quote! {
+        impl #impl_generics std::fmt::Debug for #ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                #body
+            }
+        }
+    };
+
+    if container.dump || dump_requested() {
+        dump_expansion(ast, "debug", &out)?;
+    }
+    Ok(out)
+}
+
+// A `#[debug(transparent)]` newtype prints its only field as if it were the
+// whole value, keeping the field's own format or `with` function.
+fn fmt_transparent(ident: &syn::Ident, fields: &[(&syn::Field, FieldAttrs)]) -> Result<proc_macro2::TokenStream, syn::Error> {
+    let (f, attrs) = match fields {
+        [field] => field,
+        _ => return Err(syn::Error::new_spanned(ident, "`#[debug(transparent)]` requires exactly one field")),
+    };
+    if attrs.flatten || attrs.skip_if.is_some() || attrs.rename.is_some() {
+        return Err(syn::Error::new_spanned(f, "`flatten`, `skip_if` and `rename` have no effect on a transparent field"));
+    }
+    let member = match &f.ident {
+        Some(ident) => syn::Member::Named(ident.clone()),
+        None => syn::Member::Unnamed(0.into()),
+    };
+    let out = if let Some(with) = &attrs.with {
+        quote! { #with(&self.#member, f) }
+    } else if let Some(lit) = &attrs.format {
+        quote! { f.write_fmt(::core::format_args!(#lit, &self.#member)) }
+    } else {
+        quote! { std::fmt::Debug::fmt(&self.#member, f) }
+    };
+    Ok(out)
+}
+
+fn fmt_field(f: &syn::Field, attrs: &FieldAttrs, rename_all: Option<RenameRule>, krate: &syn::Path) -> proc_macro2::TokenStream {
+    let ident = f.ident.as_ref().unwrap();
+    let name = match (&attrs.rename, rename_all) {
+        (Some(rename), _) => rename.value(),
+        (None, Some(rule)) => rule.apply(&ident.unraw().to_string()),
+        (None, None) => ident.unraw().to_string(),
+    };
+    let stmt = if attrs.flatten {
+        quote! {
+            #krate::DebugFields::debug_fields(&self.#ident, s);
+        }
+    } else if let Some(with) = &attrs.with {
+        quote! {
+            s.field(#name, &DebugWith(&self.#ident, #with));
+        }
+    } else if let Some(lit) = &attrs.format {
+        quote! {
+            s.field(#name, &::core::format_args!(#lit, &self.#ident));
+        }
+    } else {
+        quote! {
+            s.field(#name, &self.#ident);
+        }
+    };
+    match &attrs.skip_if {
+        Some(skip_if) => quote! {
+            if !#skip_if(&self.#ident) {
+                #stmt
+            }
+        },
+        None => stmt,
+    }
+}
+
+// Helper emitted into `fmt` and `debug_fields` when some field is formatted by a `with` function.
+fn debug_with_helper() -> proc_macro2::TokenStream {
+    quote! {
+        struct DebugWith<'a, T: ?Sized>(
+            &'a T,
+            fn(&T, &mut std::fmt::Formatter<'_>) -> std::fmt::Result,
+        );
+
+        impl<'a, T: ?Sized> std::fmt::Debug for DebugWith<'a, T> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                (self.1)(self.0, f)
+            }
+        }
+    }
+}
+
+// Setting this environment variable dumps every expansion, not only those of
+// types marked `#[debug(dump)]`.
+const DUMP_ENV: &str = "CUSTOM_DEBUG_DUMP";
+
+fn dump_requested() -> bool {
+    std::env::var_os(DUMP_ENV).is_some()
+}
+
+// Write the pretty-printed expansion to
+// `<target>/derive_debug/<crate>/<Type>-<hash>.<derive>.rs`. The hash is taken
+// over the type definition, so types of the same name in different modules
+// get their own file, and every crate gets its own directory.
+fn dump_expansion(ast: &DeriveInput, derive: &str, out: &proc_macro2::TokenStream) -> Result<(), syn::Error> {
+    use std::hash::{Hash, Hasher};
+
+    let ident = &ast.ident;
+    let krate = std::env::var("CARGO_CRATE_NAME").unwrap_or_else(|_| "unknown".to_owned());
+    let dir = target_dir().join("derive_debug").join(krate);
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    ast.to_token_stream().to_string().hash(&mut hasher);
+    let path = dir.join(format!("{}-{:016x}.{}.rs", ident, hasher.finish(), derive));
+    let pretty = match syn::parse2::<syn::File>(out.clone()) {
+        Ok(file) => prettyplease::unparse(&file),
+        Err(_) => out.to_string(),
+    };
+    // rustc may be expanding the same type in parallel, e.g. for a test build,
+    // so the file is written elsewhere and moved into place in one step.
+    let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+    std::fs::create_dir_all(&dir)
+        .and_then(|_| std::fs::write(&tmp, pretty))
+        .and_then(|_| std::fs::rename(&tmp, &path))
+        .map_err(|e| syn::Error::new_spanned(ident, format!("failed to dump expansion to {}: {}", path.display(), e)))
+}
+
+// The target directory Cargo builds into. Cargo doesn't tell rustc, so this is
+// `CARGO_TARGET_DIR` if it is exported, or else `target` in the workspace root,
+// the closest directory above the crate whose Cargo.toml has a `[workspace]`.
+fn target_dir() -> std::path::PathBuf {
+    if let Some(dir) = std::env::var_os("CARGO_TARGET_DIR") {
+        return dir.into();
+    }
+    let manifest_dir = match std::env::var_os("CARGO_MANIFEST_DIR") {
+        Some(dir) => std::path::PathBuf::from(dir),
+        None => return std::path::PathBuf::from("target"),
+    };
+    let root = manifest_dir.ancestors()
+        .find(|dir| {
+            std::fs::read_to_string(dir.join("Cargo.toml"))
+                .map(|manifest| manifest.lines().any(|line| line.trim() == "[workspace]"))
+                .unwrap_or(false)
+        })
+        .unwrap_or(&manifest_dir);
+    root.join("target")
+}
+
+// Casing applied to every field name by `#[debug(rename_all = "...")]`.
+#[derive(Clone, Copy)]
+enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    fn parse(lit: &syn::Lit) -> Result<Self, syn::Error> {
+        let s = lit_str(lit)?;
+        let rule = match s.value().as_str() {
+            "lowercase" => RenameRule::Lower,
+            "UPPERCASE" => RenameRule::Upper,
+            "PascalCase" => RenameRule::Pascal,
+            "camelCase" => RenameRule::Camel,
+            "snake_case" => RenameRule::Snake,
+            "SCREAMING_SNAKE_CASE" => RenameRule::ScreamingSnake,
+            "kebab-case" => RenameRule::Kebab,
+            "SCREAMING-KEBAB-CASE" => RenameRule::ScreamingKebab,
+            _ => return Err(syn::Error::new_spanned(s, "expected one of `lowercase`, `UPPERCASE`, `PascalCase`, \
+                `camelCase`, `snake_case`, `SCREAMING_SNAKE_CASE`, `kebab-case` or `SCREAMING-KEBAB-CASE`")),
+        };
+        Ok(rule)
+    }
+
+    // Apply the rule to a snake_case field name.
+    fn apply(self, name: &str) -> String {
+        let capitalize = |word: &str| {
+            let mut chars = word.chars();
+            chars.next()
+                .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        };
+        let words = name.split('_').filter(|w| !w.is_empty());
+        match self {
+            RenameRule::Lower => name.to_lowercase(),
+            RenameRule::Upper => name.to_uppercase(),
+            RenameRule::Pascal => words.map(capitalize).collect(),
+            RenameRule::Camel => {
+                let pascal = words.map(capitalize).collect::<String>();
+                let mut chars = pascal.chars();
+                chars.next()
+                    .map(|c| c.to_lowercase().chain(chars).collect())
+                    .unwrap_or_default()
+            }
+            RenameRule::Snake => name.to_string(),
+            RenameRule::ScreamingSnake => name.to_uppercase(),
+            RenameRule::Kebab => name.replace('_', "-"),
+            RenameRule::ScreamingKebab => name.replace('_', "-").to_uppercase(),
+        }
+    }
+}
+
+type Predicates = syn::punctuated::Punctuated<syn::WherePredicate, syn::Token![,]>;
+
+// Options accepted by the struct attribute, `#[debug(bound = "...")]`,
+// `#[debug(no_bound_for(Marker, path::to::Id))]`, `#[debug(name = "...")]`,
+// `#[debug(rename_all = "camelCase")]`, `#[debug(compact)]`,
+// `#[debug(transparent)]`, `#[debug(opaque = "...")]`,
+// `#[debug(with = "path::to::fn")]`, `#[debug(dump)]`, `#[debug(fields)]`
+// or `#[debug(crate = "path::to::derive_debug")]`.
+#[derive(Default)]
+struct ContainerAttrs {
+    bound: Option<Predicates>,
+    no_bound_for: Vec<syn::Path>,
+    name: Option<syn::LitStr>,
+    rename_all: Option<RenameRule>,
+    compact: bool,
+    transparent: bool,
+    opaque: Option<syn::LitStr>,
+    with: Option<syn::Path>,
+    dump: bool,
+    // Implement `DebugFields`, so the type can be flattened into others.
+    // Holds the attribute for errors.
+    fields: Option<syn::Path>,
+    // Where `DebugFields` is found, if the crate was renamed or re-exported.
+    krate: Option<syn::Path>,
+}
+
+impl ContainerAttrs {
+    fn krate(&self) -> syn::Path {
+        self.krate.clone().unwrap_or_else(|| parse_quote!(::derive_debug))
+    }
+
+    fn parse(attrs: &[syn::Attribute]) -> Result<Self, syn::Error> {
+        let mut out = ContainerAttrs::default();
+        for attr in attrs.iter().filter(|attr| attr.path.is_ident("debug")) {
+            for nested in debug_list(attr)? {
+                match &nested {
+                    syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path.is_ident("bound") => {
+                        out.bound.get_or_insert_with(Predicates::new).extend(parse_predicates(&nv.lit)?);
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::List(list)) if list.path.is_ident("no_bound_for") => {
+                        for nested in &list.nested {
+                            match nested {
+                                syn::NestedMeta::Meta(syn::Meta::Path(path)) => out.no_bound_for.push(path.clone()),
+                                _ => return Err(syn::Error::new_spanned(nested, "expected type path")),
+                            }
+                        }
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path.is_ident("name") => {
+                        out.name = Some(lit_str(&nv.lit)?.clone());
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path.is_ident("rename_all") => {
+                        out.rename_all = Some(RenameRule::parse(&nv.lit)?);
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("compact") => {
+                        out.compact = true;
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("transparent") => {
+                        out.transparent = true;
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path.is_ident("opaque") => {
+                        out.opaque = Some(lit_str(&nv.lit)?.clone());
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path.is_ident("with") => {
+                        out.with = Some(parse_lit_str(&nv.lit)?);
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("dump") => {
+                        out.dump = true;
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("fields") => {
+                        out.fields = Some(path.clone());
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path.is_ident("crate") => {
+                        out.krate = Some(parse_lit_str(&nv.lit)?);
+                    }
+                    _ => {
+                        return Err(syn::Error::new_spanned(nested, "expected one of `bound`, `no_bound_for`, `name`, \
+                            `rename_all`, `compact`, `transparent`, `opaque`, `with`, `dump`, `fields` or `crate`"));
+                    }
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+// Options accepted by the field attribute, either `#[debug = "..."]` or
+// `#[debug(with = "path::to::fn", bound = "...", rename = "...", flatten,
+// skip_if = "path::to::fn")]`.
+#[derive(Default)]
+struct FieldAttrs {
+    format: Option<syn::Lit>,
+    with: Option<syn::Path>,
+    bound: Option<Predicates>,
+    rename: Option<syn::LitStr>,
+    flatten: bool,
+    skip_if: Option<syn::Path>,
+}
+
+impl FieldAttrs {
+    fn parse(attrs: &[syn::Attribute]) -> Result<Self, syn::Error> {
+        let mut out = FieldAttrs::default();
+        for attr in attrs.iter().filter(|attr| attr.path.is_ident("debug")) {
+            if let Ok(syn::Meta::NameValue(nv)) = attr.parse_meta() {
+                out.format = Some(nv.lit);
+                continue;
+            }
+            for nested in debug_list(attr)? {
+                match &nested {
+                    syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path.is_ident("with") => {
+                        out.with = Some(parse_lit_str(&nv.lit)?);
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path.is_ident("bound") => {
+                        out.bound.get_or_insert_with(Predicates::new).extend(parse_predicates(&nv.lit)?);
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path.is_ident("rename") => {
+                        out.rename = Some(lit_str(&nv.lit)?.clone());
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("flatten") => {
+                        out.flatten = true;
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path.is_ident("skip_if") => {
+                        out.skip_if = Some(parse_lit_str(&nv.lit)?);
+                    }
+                    _ => {
+                        return Err(syn::Error::new_spanned(nested, "expected `with = \"...\"`, `bound = \"...\"`, \
+                            `rename = \"...\"`, `flatten` or `skip_if = \"...\"`"));
+                    }
+                }
+            }
+        }
+        if let (Some(format), Some(_)) = (&out.format, &out.with) {
+            return Err(syn::Error::new_spanned(format, "`debug = \"...\"` cannot be combined with `with`"));
+        }
+        if out.flatten && (out.format.is_some() || out.with.is_some() || out.rename.is_some()) {
+            let attr = attrs.iter().find(|attr| attr.path.is_ident("debug")).unwrap();
+            return Err(syn::Error::new_spanned(attr, "`flatten` cannot be combined with a format, `with` or `rename`"));
+        }
+        Ok(out)
+    }
+}
+
+// The nested items of a `#[debug(...)]` attribute.
+fn debug_list(attr: &syn::Attribute) -> Result<syn::punctuated::Punctuated<syn::NestedMeta, syn::Token![,]>, syn::Error> {
+    match attr.parse_meta()? {
+        syn::Meta::List(list) => Ok(list.nested),
+        meta => Err(syn::Error::new_spanned(meta, "expected `debug(...)`")),
+    }
+}
+
+// Parse a comma separated list of where predicates, e.g. `"T::Value: Debug, U: Debug"`.
+// An empty string yields no predicates.
+fn parse_predicates(lit: &syn::Lit) -> Result<Predicates, syn::Error> {
+    lit_str(lit)?.parse_with(Predicates::parse_terminated)
+}
+
+fn lit_str(lit: &syn::Lit) -> Result<&syn::LitStr, syn::Error> {
+    match lit {
+        syn::Lit::Str(s) => Ok(s),
+        _ => Err(syn::Error::new_spanned(lit, "expected string literal")),
+    }
+}
+
+// Parse the contents of a string literal such as `"path::to::fn"`.
+fn parse_lit_str<T: syn::parse::Parse>(lit: &syn::Lit) -> Result<T, syn::Error> {
+    lit_str(lit)?.parse()
+}
+
+// Types whose `Debug` impl does not depend on their type parameters.
+const BOUND_FREE_TYPES: &[&str] = &["PhantomData", "PhantomPinned"];
+
+// Bound every type parameter and associated type used by `types` on `bound`.
+// Lifetimes and const parameters are left as they are, they never need one.
+fn add_trait_bound(mut generics: syn::Generics, types: &[&syn::Type], bound: &syn::Path,
+                   no_bound_for: &[syn::Path]) -> syn::Generics {
+    let params = generics.type_params()
+        .map(|t| t.ident.clone())
+        .collect::<Vec<_>>();
+    let bound_free = BOUND_FREE_TYPES.iter()
+        .map(|name| syn::Path::from(syn::Ident::new(name, proc_macro2::Span::call_site())))
+        .chain(no_bound_for.iter().cloned())
+        .collect::<Vec<_>>();
+    let mut visitor = TypeParamVisitor::new(&params, &bound_free);
+    for ty in types {
+        visitor.visit_type(ty);
+    }
+    let TypeParamVisitor { used, associated_types, .. } = visitor;
+
+    for t in generics.type_params_mut() {
+        if used.contains(&t.ident) {
+            t.bounds.push(parse_quote!(#bound));
+        }
+    }
+
+    // add associated types bound into where clause
+    generics.make_where_clause();
+    let where_clause = generics.where_clause.as_mut().unwrap();
+    for t in &associated_types {
+        where_clause.predicates.push(parse_quote!(#t : #bound));
+    }
+    generics
+}
+
+// Walk a field type and collect what has to implement `Debug` (or any other
+// formatting trait) for the field to implement it: the type parameters used directly, e.g. `T` in `&'a [T]`,
+// and the associated types of type parameters, e.g. `T::Value` or
+// `<T as Trait>::Value`.
+//
+// Raw pointers, fn pointers and trait objects implement `Debug` (or not)
+// regardless of their parameters, so they are not descended into. Neither are
+// the `bound_free` types such as `PhantomData`.
+struct TypeParamVisitor<'a> {
+    params: &'a [syn::Ident],
+    bound_free: &'a [syn::Path],
+    used: Vec<syn::Ident>,
+    associated_types: Vec<syn::TypePath>,
+}
+
+impl<'a> TypeParamVisitor<'a> {
+    fn new(params: &'a [syn::Ident], bound_free: &'a [syn::Path]) -> Self {
+        TypeParamVisitor { params, bound_free, used: vec![], associated_types: vec![] }
+    }
+
+    // Whether any type parameter occurs somewhere inside `ty`.
+    fn mentions_param(&self, ty: &syn::Type) -> bool {
+        let mut inner = TypeParamVisitor::new(self.params, self.bound_free);
+        inner.visit_type(ty);
+        !inner.used.is_empty() || !inner.associated_types.is_empty()
+    }
+
+    // A path names a bound-free type if it ends with one of the listed paths,
+    // so `PhantomData` also covers `::core::marker::PhantomData<T>`.
+    fn is_bound_free(&self, path: &syn::Path) -> bool {
+        self.bound_free.iter().any(|free| {
+            free.segments.len() <= path.segments.len()
+                && free.segments.iter().rev()
+                    .zip(path.segments.iter().rev())
+                    .all(|(a, b)| a.ident == b.ident)
+        })
+    }
+
+    fn push_associated_type(&mut self, ty: &syn::TypePath) {
+        let s = ty.to_token_stream().to_string();
+        if !self.associated_types.iter().any(|t| t.to_token_stream().to_string() == s) {
+            self.associated_types.push(ty.clone());
+        }
+    }
+}
+
+impl<'a, 'ast> Visit<'ast> for TypeParamVisitor<'a> {
+    fn visit_type_path(&mut self, ty: &'ast syn::TypePath) {
+        // `<T as Trait>::Value`, or any other qualified path built on a type
+        // parameter, is bounded as a whole.
+        if let Some(qself) = &ty.qself {
+            if self.mentions_param(&qself.ty) {
+                self.push_associated_type(ty);
+            }
+            return;
+        }
+
+        if self.is_bound_free(&ty.path) {
+            return;
+        }
+
+        let segments = &ty.path.segments;
+        if let Some(segment) = segments.first() {
+            if ty.path.leading_colon.is_none() && self.params.contains(&segment.ident) {
+                if segments.len() > 1 {
+                    // `T::Value`, `T::Value::Inner`
+                    self.push_associated_type(ty);
+                } else if !self.used.contains(&segment.ident) {
+                    self.used.push(segment.ident.clone());
+                }
+                return;
+            }
+        }
+
+        // Delegate to the default impl to visit generic arguments.
+        syn::visit::visit_type_path(self, ty);
+    }
+
+    fn visit_type_ptr(&mut self, _: &'ast syn::TypePtr) {}
+
+    fn visit_type_bare_fn(&mut self, _: &'ast syn::TypeBareFn) {}
+
+    fn visit_type_trait_object(&mut self, _: &'ast syn::TypeTraitObject) {}
+
+    fn visit_type_impl_trait(&mut self, _: &'ast syn::TypeImplTrait) {}
+
+    // Array lengths and const generic arguments are not types.
+    fn visit_expr(&mut self, _: &'ast syn::Expr) {}
+}
+
+fn add_trait_bound_with_escape_hatch(mut generics: syn::Generics, escape_hatch: &Predicates) -> Result<syn::Generics, syn::Error> {
+    for predicate in escape_hatch {
+        validate_predicate(&generics, predicate)?;
+    }
+    generics.make_where_clause();
+    let where_clause = generics.where_clause.as_mut().unwrap();
+    // eprintln!(">>> hatch: {}", escape_hatch.to_token_stream());
+    where_clause.predicates.extend(escape_hatch.iter().cloned());
+    Ok(generics)
+}
+
+// Reject handwritten predicates that bound a const parameter or use a lifetime
+// the type does not declare, which rustc would otherwise report far from the
+// attribute.
+fn validate_predicate(generics: &syn::Generics, predicate: &syn::WherePredicate) -> Result<(), syn::Error> {
+    if let syn::WherePredicate::Type(syn::PredicateType { bounded_ty: syn::Type::Path(ty), .. }) = predicate {
+        if let Some(ident) = ty.path.get_ident() {
+            if generics.const_params().any(|c| c.ident == *ident) {
+                return Err(syn::Error::new_spanned(ty, format!("`{}` is a const parameter and cannot be bounded", ident)));
+            }
+        }
+    }
+
+    let mut lifetimes = LifetimeVisitor::default();
+    lifetimes.visit_where_predicate(predicate);
+    for lifetime in &lifetimes.used {
+        let declared = lifetime.ident == "static"
+            || generics.lifetimes().any(|l| l.lifetime == *lifetime)
+            || lifetimes.bound.contains(lifetime);
+        if !declared {
+            return Err(syn::Error::new_spanned(lifetime, format!("use of undeclared lifetime `{}`", lifetime)));
+        }
+    }
+    Ok(())
+}
+
+// Lifetimes used in a predicate, and the ones it binds itself with `for<'x>`.
+#[derive(Default)]
+struct LifetimeVisitor {
+    used: Vec<syn::Lifetime>,
+    bound: Vec<syn::Lifetime>,
+}
+
+impl<'ast> Visit<'ast> for LifetimeVisitor {
+    fn visit_lifetime(&mut self, i: &'ast syn::Lifetime) {
+        self.used.push(i.clone());
+    }
+
+    fn visit_bound_lifetimes(&mut self, i: &'ast syn::BoundLifetimes) {
+        self.bound.extend(i.lifetimes.iter().map(|def| def.lifetime.clone()));
+        syn::visit::visit_bound_lifetimes(self, i);
+    }
+}
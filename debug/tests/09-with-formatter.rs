@@ -0,0 +1,53 @@
+// Some fields are better rendered by a function than by a format string, for
+// example byte buffers as hex or large collections as a short summary. The
+// `debug(with = "...")` attribute names a function with the signature
+//
+//     fn(&FieldType, &mut fmt::Formatter) -> fmt::Result
+//
+// which is called in place of the field's own `Debug` impl. Such a field does
+// not require its type to implement `Debug`.
+
+use derive_debug::CustomDebug;
+use std::fmt::{self, Debug};
+
+mod fmt_helpers {
+    use std::fmt;
+
+    pub fn hex(bytes: &Vec<u8>, f: &mut fmt::Formatter) -> fmt::Result {
+        for b in bytes {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+fn summary<T>(values: &Vec<T>, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "[{} items]", values.len())
+}
+
+#[derive(CustomDebug)]
+pub struct Packet<T> {
+    id: u32,
+    #[debug(with = "fmt_helpers::hex")]
+    payload: Vec<u8>,
+    #[debug(with = "summary")]
+    items: Vec<T>,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+
+    let p = Packet {
+        id: 7,
+        payload: vec![0xde, 0xad, 0xbe, 0xef],
+        items: vec![NotDebug, NotDebug],
+    };
+
+    let debug = format!("{:?}", p);
+    let expected = "Packet { id: 7, payload: deadbeef, items: [2 items] }";
+    assert_eq!(debug, expected);
+
+    assert_debug::<Packet<NotDebug>>();
+}
@@ -0,0 +1,76 @@
+// The `debug(bound = "...")` escape hatch accepts any number of comma
+// separated where-predicates, and an empty string turns off bound inference
+// entirely.
+//
+// The same attribute is also accepted on individual fields. It replaces only
+// the bounds that would have been inferred from that field's type, while the
+// bounds inferred from the other fields are kept:
+//
+//     impl<T: Trait, U: Debug> Debug for Wrapper<T, U>
+//     where
+//         T::Value: Debug,
+//         T::Key: Debug,
+//     {...}
+//
+// Field predicates are added to the container's own `bound` as well, rather
+// than being replaced by it.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+pub trait Trait {
+    type Value;
+    type Key;
+}
+
+#[derive(CustomDebug)]
+struct Field<T: Trait> {
+    values: Vec<T::Value>,
+    keys: Vec<T::Key>,
+}
+
+#[derive(CustomDebug)]
+pub struct Wrapper<T: Trait, U> {
+    #[debug(bound = "T::Value: Debug, T::Key: Debug")]
+    field: Field<T>,
+    normal: U,
+}
+
+#[derive(CustomDebug)]
+#[debug(bound = "T::Value: Debug, T::Key: Debug,")]
+pub struct Pair<T: Trait> {
+    left: Field<T>,
+    right: Field<T>,
+}
+
+#[derive(CustomDebug)]
+#[debug(bound = "U: Debug")]
+pub struct Mixed<T: Trait, U> {
+    #[debug(bound = "T::Value: Debug, T::Key: Debug")]
+    field: Field<T>,
+    normal: U,
+}
+
+#[derive(CustomDebug)]
+#[debug(bound = "")]
+pub struct Unbounded<T> {
+    marker: PhantomData<fn(T)>,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct Id;
+    struct NotDebug;
+
+    impl Trait for Id {
+        type Value = u8;
+        type Key = String;
+    }
+
+    assert_debug::<Wrapper<Id, u8>>();
+    assert_debug::<Pair<Id>>();
+    assert_debug::<Mixed<Id, u8>>();
+    assert_debug::<Unbounded<NotDebug>>();
+}
@@ -0,0 +1,18 @@
+// A `bound` attribute whose string is not a list of where-predicates should
+// produce an error pointing at the string rather than panicking.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(bound = "T: Debug, T Clone")]
+pub struct Wrapper<T> {
+    field: T,
+}
+
+#[derive(CustomDebug)]
+pub struct Other<T> {
+    #[debug(bound = "T Debug")]
+    field: T,
+}
+
+fn main() {}
@@ -0,0 +1,81 @@
+// Bound inference has to look through every kind of type a field can have,
+// not only paths with angle bracketed arguments:
+//
+//     &'a T, &'a mut T          -> T: Debug
+//     (T, U), [T; N], [T]       -> T: Debug, U: Debug
+//     <T as Trait>::Value       -> <T as Trait>::Value: Debug
+//     <T::Inner as Inner>::Leaf -> <T::Inner as Inner>::Leaf: Debug
+//
+// Raw pointers, fn pointers and trait objects implement Debug or not on their
+// own, so a type parameter only mentioned inside them does not need a bound.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+pub trait Trait {
+    type Value;
+    type Inner: Inner;
+}
+
+pub trait Inner {
+    type Leaf;
+}
+
+#[derive(CustomDebug)]
+pub struct Shapes<'a, A, B, C, D> {
+    reference: &'a A,
+    tuple: (B, u8),
+    array: [C; 2],
+    slice: &'a [D],
+}
+
+#[derive(CustomDebug)]
+pub struct Pointers<A, B, C> {
+    raw: *const A,
+    callback: fn(B) -> C,
+}
+
+#[derive(CustomDebug)]
+pub struct Boxed<'a, T> {
+    value: &'a mut Box<T>,
+}
+
+#[derive(CustomDebug)]
+pub struct Qualified<T: Trait> {
+    value: Option<<T as Trait>::Value>,
+    nested: (<T::Inner as Inner>::Leaf,),
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+    struct Id;
+    struct Node;
+
+    impl Trait for Id {
+        type Value = u8;
+        type Inner = Node;
+    }
+
+    impl Inner for Node {
+        type Leaf = String;
+    }
+
+    assert_debug::<Shapes<u8, u16, u32, u64>>();
+    assert_debug::<Pointers<NotDebug, NotDebug, NotDebug>>();
+    assert_debug::<Boxed<u8>>();
+    assert_debug::<Qualified<Id>>();
+
+    let value = 1;
+    let slice = [2, 3];
+    let shapes = Shapes {
+        reference: &value,
+        tuple: (4, 5),
+        array: [6, 7],
+        slice: &slice,
+    };
+    let debug = format!("{:?}", shapes);
+    let expected = "Shapes { reference: 1, tuple: (4, 5), array: [6, 7], slice: [2, 3] }";
+    assert_eq!(debug, expected);
+}
@@ -0,0 +1,51 @@
+// PhantomData is not the only type whose Debug impl does not care about its
+// type parameters. Such types can be listed in a container attribute, and
+// every type parameter that only appears inside them is left unbounded:
+//
+//     #[debug(no_bound_for(Marker, ids::Id))]
+//
+// A listed path matches any type path ending in it, and the built-in
+// PhantomData and PhantomPinned are recognized however they are qualified.
+
+use derive_debug::CustomDebug;
+use std::fmt::{self, Debug};
+use std::marker::PhantomPinned;
+
+pub struct Marker<T>(std::marker::PhantomData<T>);
+
+impl<T> Debug for Marker<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Marker")
+    }
+}
+
+pub mod ids {
+    use std::fmt::{self, Debug};
+    use std::marker::PhantomData;
+
+    pub struct Id<T>(pub u32, pub PhantomData<T>);
+
+    impl<T> Debug for Id<T> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "#{}", self.0)
+        }
+    }
+}
+
+#[derive(CustomDebug)]
+#[debug(no_bound_for(Marker, ids::Id))]
+pub struct Field<A, B, C, D> {
+    marker: Marker<A>,
+    id: self::ids::Id<B>,
+    phantom: ::core::marker::PhantomData<C>,
+    std_phantom: std::marker::PhantomData<D>,
+    pinned: PhantomPinned,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+
+    assert_debug::<Field<NotDebug, NotDebug, NotDebug, NotDebug>>();
+}
@@ -0,0 +1,105 @@
+// The crate also provides a CustomDisplay derive for the common case of a
+// Display impl that is only a format string over the fields. The template goes
+// in a `display("...")` attribute on the struct, or on each enum variant, and
+// refers to fields by name or, for tuple fields, by index:
+//
+//     #[derive(CustomDisplay)]
+//     #[display("{name} ({id:#x})")]
+//     pub struct User {
+//         name: String,
+//         id: u32,
+//     }
+//
+// Unit variants without a template print the variant name. A raw identifier
+// field such as `r#type` can be written as `{type}` or `{r#type}`.
+//
+// Bounds are inferred the same way as for CustomDebug, but only from the
+// fields the template mentions, and on the formatting trait each is used
+// with: `T: Display` for `{value}`, `T: LowerHex` for `{value:x}`, and so on.
+// `{value:p}` is the exception, it needs the whole field type to implement
+// `Pointer`, as in `&'a T: Pointer`, which holds for any `T`.
+
+use derive_debug::CustomDisplay;
+use std::fmt::Display;
+
+#[derive(CustomDisplay)]
+#[display("{name} ({id:#x})")]
+pub struct User {
+    name: String,
+    id: u32,
+}
+
+#[derive(CustomDisplay)]
+#[display("({0}, {1})")]
+pub struct Point(i32, i32);
+
+#[derive(CustomDisplay)]
+pub enum Shape<T> {
+    #[display("circle r={radius:.1}")]
+    Circle { radius: f64 },
+    #[display("rect {0}x{1}")]
+    Rect(T, T),
+    #[display("{{empty}}")]
+    Empty,
+    Unknown,
+}
+
+#[derive(CustomDisplay)]
+#[display("{value:>width$x}")]
+pub struct Hex<T, U> {
+    value: T,
+    width: usize,
+    // Not mentioned in the template, so U is not bounded.
+    extra: U,
+}
+
+#[derive(CustomDisplay)]
+#[display("{type}/{r#type:03}")]
+pub struct Token {
+    r#type: u8,
+}
+
+#[derive(CustomDisplay)]
+#[display("{target:p}")]
+pub struct Ptr<'a, T> {
+    target: &'a T,
+}
+
+#[derive(CustomDisplay)]
+pub enum Never {}
+
+fn assert_display<F: Display>() {}
+
+fn main() {
+    struct NotDisplay;
+
+    let user = User {
+        name: "ferris".to_owned(),
+        id: 255,
+    };
+    assert_eq!(user.to_string(), "ferris (0xff)");
+
+    assert_eq!(Point(1, -2).to_string(), "(1, -2)");
+
+    assert_eq!(Shape::<u8>::Circle { radius: 1.25 }.to_string(), "circle r=1.2");
+    assert_eq!(Shape::Rect(3, 4).to_string(), "rect 3x4");
+    assert_eq!(Shape::<u8>::Empty.to_string(), "{empty}");
+    assert_eq!(Shape::<u8>::Unknown.to_string(), "Unknown");
+
+    let hex = Hex {
+        value: 0xabu8,
+        width: 4,
+        extra: NotDisplay,
+    };
+    assert_eq!(hex.to_string(), "  ab");
+
+    assert_display::<Hex<u8, NotDisplay>>();
+
+    assert_eq!(Token { r#type: 7 }.to_string(), "7/007");
+
+    let target = 5u8;
+    let ptr = Ptr { target: &target };
+    assert_eq!(ptr.to_string(), format!("{:p}", &target));
+    assert_display::<Ptr<'static, NotDisplay>>();
+    assert_display::<Never>();
+}
@@ -0,0 +1,26 @@
+// Placeholders in a CustomDisplay template must name a field of the struct or
+// variant. Anything else is reported at the template string, as is a template
+// on an enum rather than on its variants.
+
+use derive_debug::CustomDisplay;
+
+#[derive(CustomDisplay)]
+#[display("{name} ({ident})")]
+pub struct User {
+    name: String,
+    id: u32,
+}
+
+#[derive(CustomDisplay)]
+#[display("{} ({})")]
+pub struct Point(i32, i32);
+
+#[derive(CustomDisplay)]
+#[display("{0}")]
+pub enum Either {
+    #[display("left {0}")]
+    Left(u8),
+    Right,
+}
+
+fn main() {}
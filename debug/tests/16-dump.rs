@@ -0,0 +1,49 @@
+// Expansions are not printed during the build. To inspect what the derive
+// generates for one type, mark it with `debug(dump)` and the pretty-printed
+// impl is written to `derive_debug/<crate>/<Type>-<hash>.debug.rs` under the
+// target directory. The hash tells apart types of the same name in different
+// modules. Setting the CUSTOM_DEBUG_DUMP environment variable dumps every
+// expansion.
+
+use derive_debug::CustomDebug;
+use std::path::{Path, PathBuf};
+
+#[derive(CustomDebug)]
+#[debug(dump)]
+pub struct Dumped<T> {
+    value: T,
+}
+
+mod other {
+    use derive_debug::CustomDebug;
+
+    #[derive(CustomDebug)]
+    #[debug(dump)]
+    pub struct Dumped {
+        pub id: u32,
+    }
+}
+
+fn main() {
+    let target = option_env!("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("target"));
+    let dir = target.join("derive_debug").join(env!("CARGO_CRATE_NAME"));
+
+    let dumps = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            let name = path.file_name().unwrap().to_str().unwrap();
+            name.starts_with("Dumped-") && name.ends_with(".debug.rs")
+        })
+        .map(|path| std::fs::read_to_string(path).unwrap())
+        .collect::<Vec<_>>();
+    let generic = "impl<T: std::fmt::Debug> std::fmt::Debug for Dumped<T> {";
+    let plain = "impl std::fmt::Debug for Dumped {";
+    assert!(dumps.iter().any(|dump| dump.contains(generic)));
+    assert!(dumps.iter().any(|dump| dump.contains(plain)));
+
+    assert_eq!(format!("{:?}", Dumped { value: 1 }), "Dumped { value: 1 }");
+    assert_eq!(format!("{:?}", other::Dumped { id: 2 }), "Dumped { id: 2 }");
+}
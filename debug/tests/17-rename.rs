@@ -0,0 +1,50 @@
+// The names printed for the type and its fields can be changed without
+// renaming them in the source, which keeps log output stable and hides
+// internal naming:
+//
+//     #[debug(name = "...")]          on the struct replaces the type name
+//     #[debug(rename = "...")]        on a field replaces that field's name
+//     #[debug(rename_all = "...")]    on the struct changes the case of every
+//                                     field that is not renamed explicitly
+//
+// The supported cases are the ones serde uses: lowercase, UPPERCASE,
+// PascalCase, camelCase, snake_case, SCREAMING_SNAKE_CASE, kebab-case and
+// SCREAMING-KEBAB-CASE.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(name = "Request", rename_all = "camelCase")]
+pub struct RawRequest {
+    request_id: u32,
+    #[debug(rename = "path")]
+    raw_path: &'static str,
+    #[debug(rename = "retries", with = "count")]
+    retry_count_total: u8,
+    r#type: u8,
+}
+
+#[derive(CustomDebug)]
+#[debug(rename_all = "SCREAMING-KEBAB-CASE")]
+pub struct Header {
+    content_length: usize,
+}
+
+fn count(n: &u8, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(f, "{}x", n)
+}
+
+fn main() {
+    let request = RawRequest {
+        request_id: 1,
+        raw_path: "/",
+        retry_count_total: 3,
+        r#type: 0,
+    };
+    let debug = format!("{:?}", request);
+    let expected = r#"Request { requestId: 1, path: "/", retries: 3x, type: 0 }"#;
+    assert_eq!(debug, expected);
+
+    let header = Header { content_length: 5 };
+    assert_eq!(format!("{:?}", header), "Header { CONTENT-LENGTH: 5 }");
+}
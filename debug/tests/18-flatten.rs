@@ -0,0 +1,88 @@
+// Composed structs print as nested structs by default:
+//
+//     Outer { id: 1, inner: Inner { a: 2, b: 3 } }
+//
+// Marking a field with `debug(flatten)` splices the fields of the nested
+// struct into the parent instead:
+//
+//     Outer { id: 1, a: 2, b: 3 }
+//
+// This works through the derive_debug::DebugFields trait, which CustomDebug
+// implements for structs marked `debug(fields)`. Other types don't refer to
+// the derive_debug crate at all, so it can be renamed in Cargo.toml, and
+// `debug(crate = "...")` says where to find the trait when it is. Flattened
+// types can be generic, including a bare type parameter, and are bounded on
+// DebugFields rather than Debug:
+//
+//     impl<T> Debug for Generic<T> where T: DebugFields {...}
+
+use derive_debug::{CustomDebug, DebugFields};
+use derive_debug as dd;
+use std::fmt::Debug;
+
+#[derive(CustomDebug)]
+#[debug(fields, crate = "dd")]
+pub struct Inner<T> {
+    a: T,
+    #[debug = "0x{:02x}"]
+    b: u8,
+}
+
+#[derive(CustomDebug)]
+#[debug(fields)]
+pub struct Outer<T> {
+    id: u32,
+    #[debug(flatten)]
+    inner: Inner<T>,
+    last: bool,
+}
+
+#[derive(CustomDebug)]
+pub struct Nested {
+    #[debug(flatten)]
+    outer: Outer<&'static str>,
+}
+
+#[derive(CustomDebug)]
+pub struct Generic<T> {
+    #[debug(flatten)]
+    inner: T,
+}
+
+fn assert_debug_fields<F: DebugFields>() {}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    let outer = Outer {
+        id: 1,
+        inner: Inner { a: 2, b: 3 },
+        last: true,
+    };
+    let debug = format!("{:?}", outer);
+    let expected = "Outer { id: 1, a: 2, b: 0x03, last: true }";
+    assert_eq!(debug, expected);
+
+    let nested = Nested {
+        outer: Outer {
+            id: 1,
+            inner: Inner { a: "x", b: 255 },
+            last: false,
+        },
+    };
+    let debug = format!("{:?}", nested);
+    let expected = r#"Nested { id: 1, a: "x", b: 0xff, last: false }"#;
+    assert_eq!(debug, expected);
+
+    assert_debug_fields::<Outer<u8>>();
+    assert_debug::<Outer<u8>>();
+
+    let generic = Generic {
+        inner: Inner { a: 'x', b: 16 },
+    };
+    let debug = format!("{:?}", generic);
+    let expected = "Generic { a: 'x', b: 0x10 }";
+    assert_eq!(debug, expected);
+
+    assert_debug::<Generic<Inner<u8>>>();
+}
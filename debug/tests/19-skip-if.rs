@@ -0,0 +1,43 @@
+// Structs with many optional or collection fields print a lot of noise. A
+// `debug(skip_if = "...")` attribute names a predicate taking a reference to
+// the field, and the field is left out of the output whenever the predicate
+// returns true for its current value:
+//
+//     #[debug(skip_if = "Option::is_none")]
+//     parent: Option<u32>,
+
+use derive_debug::CustomDebug;
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+#[derive(CustomDebug)]
+pub struct Node {
+    id: u32,
+    #[debug(skip_if = "Option::is_none")]
+    parent: Option<u32>,
+    #[debug(skip_if = "Vec::is_empty")]
+    children: Vec<u32>,
+    #[debug(skip_if = "is_zero", rename = "w")]
+    weight: u32,
+}
+
+fn main() {
+    let leaf = Node {
+        id: 1,
+        parent: None,
+        children: vec![],
+        weight: 0,
+    };
+    assert_eq!(format!("{:?}", leaf), "Node { id: 1 }");
+
+    let node = Node {
+        id: 2,
+        parent: Some(1),
+        children: vec![3, 4],
+        weight: 5,
+    };
+    let expected = "Node { id: 2, parent: Some(1), children: [3, 4], w: 5 }";
+    assert_eq!(format!("{:?}", node), expected);
+}
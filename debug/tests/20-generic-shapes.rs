@@ -0,0 +1,58 @@
+// Structs can mix lifetimes, type parameters with defaults and const
+// generics, also with defaults. Lifetimes and const parameters never need a
+// Debug bound; only the type parameters that fields use do:
+//
+//     impl<'a, T: Debug, const N: usize> Debug for Foo<'a, T, N> {...}
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+#[derive(CustomDebug)]
+pub struct Foo<'a, T, const N: usize = 4> {
+    values: [&'a T; N],
+}
+
+#[derive(CustomDebug)]
+pub struct Defaults<'a, 'b: 'a, A = u8, B: Clone = String, const LEN: usize = 2>
+where
+    A: 'a,
+{
+    first: &'a A,
+    second: &'b [B; LEN],
+}
+
+#[derive(CustomDebug)]
+pub struct Marked<'a, T: 'a + ?Sized, U = (), const ID: u8 = 0> {
+    marker: PhantomData<&'a T>,
+    extra: U,
+}
+
+#[derive(CustomDebug)]
+#[debug(bound = "T: Debug + 'a")]
+pub struct Hatch<'a, T, const N: usize> {
+    values: [Option<&'a T>; N],
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+
+    let (a, b) = (1, 2);
+    let foo = Foo { values: [&a, &b] };
+    assert_eq!(format!("{:?}", foo), "Foo { values: [1, 2] }");
+    assert_debug::<Foo<u8>>();
+
+    let second = ["x".to_owned(), "y".to_owned()];
+    let defaults: Defaults = Defaults {
+        first: &0,
+        second: &second,
+    };
+    let debug = format!("{:?}", defaults);
+    assert_eq!(debug, r#"Defaults { first: 0, second: ["x", "y"] }"#);
+
+    assert_debug::<Marked<NotDebug>>();
+    assert_debug::<Marked<str, u8, 3>>();
+    assert_debug::<Hatch<u8, 8>>();
+}
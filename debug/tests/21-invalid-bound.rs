@@ -0,0 +1,30 @@
+// Handwritten bounds are checked against the type's generics, so that a
+// bound on a const parameter or on a lifetime the type does not declare is
+// reported at the attribute.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(bound = "T: Debug, N: Debug")]
+pub struct Array<T, const N: usize> {
+    values: [T; N],
+}
+
+#[derive(CustomDebug)]
+pub struct Borrowed<'a, T> {
+    #[debug(bound = "&'b T: Debug")]
+    value: &'a T,
+}
+
+#[derive(CustomDebug)]
+#[debug(bound = "T: for<'x> Fn(&'x u8), &'a T: 'static")]
+pub struct Callback<'a, T> {
+    #[debug(with = "opaque")]
+    callback: &'a T,
+}
+
+fn opaque<T>(_: &T, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    f.write_str("..")
+}
+
+fn main() {}
@@ -0,0 +1,63 @@
+// Small value types such as ids and coordinates are easier to read when they
+// stay on one line inside a large `{:#?}` tree. With `debug(compact)` the
+// derived impl ignores the alternate flag and always prints on one line.
+//
+// Newtypes marked `debug(transparent)` print just their only field, through
+// its own Debug impl or the field's format string or `with` function.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(compact)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Id(u32);
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Mask {
+    #[debug = "0b{:04b}"]
+    bits: u8,
+}
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Wrapper<T>(T);
+
+#[derive(CustomDebug)]
+pub struct Shape {
+    id: Id,
+    origin: Point,
+    mask: Mask,
+    tags: Wrapper<Vec<&'static str>>,
+}
+
+fn main() {
+    let point = Point { x: 1, y: -2 };
+    assert_eq!(format!("{:?}", point), "Point { x: 1, y: -2 }");
+    assert_eq!(format!("{:#?}", point), "Point { x: 1, y: -2 }");
+
+    assert_eq!(format!("{:?}", Id(7)), "7");
+    assert_eq!(format!("{:?}", Mask { bits: 5 }), "0b0101");
+
+    let shape = Shape {
+        id: Id(3),
+        origin: point,
+        mask: Mask { bits: 1 },
+        tags: Wrapper(vec!["a"]),
+    };
+    let expected = r#"Shape {
+    id: 3,
+    origin: Point { x: 1, y: -2 },
+    mask: 0b0001,
+    tags: [
+        "a",
+    ],
+}"#;
+    assert_eq!(format!("{:#?}", shape), expected);
+}
@@ -0,0 +1,50 @@
+// Reading a union field is unsafe, so unions are only accepted when the
+// derive does not have to look at the fields. With `debug(opaque = "...")`
+// the output is the type name, the given summary and the size of the type:
+//
+//     IntOrFloat(int or float, 4 bytes)
+//
+// Alternatively `debug(with = "...")` on the type hands the whole value to a
+// function `fn(&Self, &mut fmt::Formatter) -> fmt::Result`, which may know
+// which field is active. Both also work on structs whose contents should stay
+// hidden.
+
+use derive_debug::CustomDebug;
+use std::fmt;
+
+#[derive(CustomDebug)]
+#[debug(opaque = "int or float")]
+pub union IntOrFloat {
+    i: u32,
+    f: f32,
+}
+
+#[derive(CustomDebug)]
+#[debug(with = "fmt_tagged")]
+pub union Tagged {
+    bits: u64,
+    bytes: [u8; 8],
+}
+
+fn fmt_tagged(value: &Tagged, f: &mut fmt::Formatter) -> fmt::Result {
+    // Every bit pattern is a valid u64.
+    write!(f, "Tagged({:#x})", unsafe { value.bits })
+}
+
+#[derive(CustomDebug)]
+#[debug(opaque = "redacted", name = "Secret")]
+pub struct Password<T> {
+    value: T,
+}
+
+fn main() {
+    let value = IntOrFloat { i: 1 };
+    assert_eq!(format!("{:?}", value), "IntOrFloat(int or float, 4 bytes)");
+
+    let tagged = Tagged { bits: 0xff };
+    assert_eq!(format!("{:?}", tagged), "Tagged(0xff)");
+
+    struct NotDebug;
+    let password = Password { value: NotDebug };
+    assert_eq!(format!("{:?}", password), "Secret(redacted, 0 bytes)");
+}
@@ -0,0 +1,11 @@
+// Without `opaque` or `with` there is no safe way to print a union.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub union IntOrFloat {
+    i: u32,
+    f: f32,
+}
+
+fn main() {}
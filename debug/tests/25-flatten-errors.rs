@@ -0,0 +1,27 @@
+// Only structs marked `debug(fields)` can be flattened. Transparent and opaque
+// types have no fields of their own, so they can't be marked.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Plain {
+    a: u8,
+}
+
+#[derive(CustomDebug)]
+pub struct Outer {
+    #[debug(flatten)]
+    plain: Plain,
+}
+
+#[derive(CustomDebug)]
+#[debug(transparent, fields)]
+pub struct Id(u32);
+
+#[derive(CustomDebug)]
+#[debug(opaque = "secret", fields)]
+pub struct Key {
+    bytes: [u8; 4],
+}
+
+fn main() {}
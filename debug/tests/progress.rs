@@ -1,12 +1,29 @@
 #[test]
 fn tests() {
     let t = trybuild::TestCases::new();
-    //t.pass("tests/01-parse.rs");
-    //t.pass("tests/02-impl-debug.rs");
-    //t.pass("tests/03-custom-format.rs");
-    //t.pass("tests/04-type-parameter.rs");
-    //t.pass("tests/05-phantom-data.rs");
-    //t.pass("tests/06-bound-trouble.rs");
-    //t.pass("tests/07-associated-type.rs");
-    //t.pass("tests/08-escape-hatch.rs");
+    t.pass("tests/01-parse.rs");
+    t.pass("tests/02-impl-debug.rs");
+    t.pass("tests/03-custom-format.rs");
+    t.pass("tests/04-type-parameter.rs");
+    t.pass("tests/05-phantom-data.rs");
+    t.pass("tests/06-bound-trouble.rs");
+    t.pass("tests/07-associated-type.rs");
+    t.pass("tests/08-escape-hatch.rs");
+    t.pass("tests/09-with-formatter.rs");
+    t.pass("tests/10-field-bound.rs");
+    t.compile_fail("tests/11-malformed-bound.rs");
+    t.pass("tests/12-type-shapes.rs");
+    t.pass("tests/13-no-bound-for.rs");
+    t.pass("tests/14-display.rs");
+    t.compile_fail("tests/15-display-unknown-field.rs");
+    t.pass("tests/16-dump.rs");
+    t.pass("tests/17-rename.rs");
+    t.pass("tests/18-flatten.rs");
+    t.pass("tests/19-skip-if.rs");
+    t.pass("tests/20-generic-shapes.rs");
+    t.compile_fail("tests/21-invalid-bound.rs");
+    t.pass("tests/22-compact-transparent.rs");
+    t.pass("tests/23-opaque-union.rs");
+    t.compile_fail("tests/24-union-without-opaque.rs");
+    t.compile_fail("tests/25-flatten-errors.rs");
 }
@@ -1,57 +1,737 @@
 use proc_macro::TokenStream;
-use proc_macro2::TokenTree;
+use proc_macro2::{Punct, Spacing, Span, TokenTree};
 use proc_macro2::Delimiter;
 use syn::parse::{Parse, ParseStream};
-use syn::{Ident, Token, LitInt, braced};
+use syn::punctuated::Punctuated;
+use syn::{Ident, Token, LitInt, Lit, Expr, BinOp, UnOp, braced, bracketed, parenthesized, token};
 use syn::parse_macro_input;
-use syn::buffer::{TokenBuffer, Cursor};
-use quote::quote;
+use quote::{format_ident, quote, quote_spanned};
 
 struct SeqInput {
+    vars: Vec<SeqVar>,
+    // `N in 0..8, M in 8..16 zip` walks the ranges in lock-step. Otherwise
+    // every combination of values is visited, the first variable varying
+    // slowest.
+    zip: bool,
+    content: proc_macro2::TokenStream,
+}
+
+// A loop variable and the range of values it takes.
+struct SeqVar {
     ident: Ident,
+    // `T in [u8, u16]` walks the indices of the list and substitutes the
+    // item at each one. The index itself is available as `T_index`.
+    items: Option<(Vec<proc_macro2::TokenStream>, Ident)>,
     // in_token: syn::Token![in],
-    begin: isize,
+    begin: i128,
     // range_token: syn::Token![..],
-    end: isize,
-    content: proc_macro2::TokenStream,
+    end: i128,
+    // `begin..=end`. Kept apart from `end` so that `..=i128::MAX` works.
+    inclusive: bool,
+    // How the bounds were written, reused for the emitted literals.
+    repr: IntRepr,
+    // `(begin..end).rev()`.
+    rev: bool,
+    // `begin..end step N`.
+    step: u128,
+    // The range as written, for errors about its size.
+    span: Span,
 }
 
 impl Parse for SeqInput {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let ident: Ident = input.parse()?;
-        input.parse::<Token![in]>()?;
-        let begin: LitInt = input.parse()?;
-        input.parse::<Token![..]>()?;
-        // For test 7.
-        let inclusive_range = if input.peek(Token![=]) {
-            eprintln!("match inclusive range");
-            input.parse::<Token![=]>()?;
-            true
+        let mut vars = Vec::new();
+        let mut zip = false;
+        if input.peek(token::Paren) {
+            // `(I, J) in (0..4) x (0..4)`
+            let names;
+            let paren = parenthesized!(names in input);
+            let names = Punctuated::<Ident, Token![,]>::parse_terminated(&names)?;
+            input.parse::<Token![in]>()?;
+            let mut ranges = vec![parse_range_spec(input)?];
+            while peek_keyword(input, "x") {
+                input.parse::<Ident>()?;
+                ranges.push(parse_range_spec(input)?);
+            }
+            if names.len() != ranges.len() {
+                let msg = format!("expected {} variables, one for each range, found {}", ranges.len(), names.len());
+                return Err(syn::Error::new(paren.span, msg));
+            }
+            for (ident, range) in names.into_iter().zip(ranges) {
+                vars.push(range(ident));
+            }
+        } else {
+            // `N in 0..8` or `N in 0..8, M in 8..16 zip`
+            loop {
+                let ident: Ident = input.parse()?;
+                input.parse::<Token![in]>()?;
+                vars.push(parse_range_spec(input)?(ident));
+                if input.parse::<Option<Token![,]>>()?.is_none() {
+                    break;
+                }
+            }
+            if vars.len() > 1 {
+                let keyword: Ident = input.parse()?;
+                if keyword != "zip" {
+                    return Err(syn::Error::new_spanned(keyword, "expected `zip` after the list of ranges"));
+                }
+                let len = vars[0].len();
+                if let Some(var) = vars.iter().find(|var| var.len() != len) {
+                    let msg = format!("cannot zip ranges of different lengths, `{}` has {} values and `{}` has {}", vars[0].ident, len, var.ident, var.len());
+                    return Err(syn::Error::new_spanned(keyword, msg));
+                }
+                zip = true;
+            }
+        }
+        for (i, var) in vars.iter().enumerate() {
+            if vars[..i].iter().any(|other| other.ident == var.ident) {
+                return Err(syn::Error::new_spanned(&var.ident, format!("variable `{}` is bound more than once", var.ident)));
+            }
+        }
+        let iterations = if zip {
+            vars[0].len()
         } else {
-            false
+            vars.iter().fold(1u128, |total, var| total.saturating_mul(var.len()))
         };
-        let end: LitInt = input.parse()?;
+        let limit = max_iterations()?;
+        if iterations > limit {
+            let largest = vars.iter().max_by_key(|var| var.len()).unwrap();
+            let msg = format!(
+                "`seq!` would expand to {} iterations, more than the limit of {}; set {} to raise it",
+                iterations, limit, MAX_ITERATIONS_ENV,
+            );
+            return Err(syn::Error::new(largest.span, msg));
+        }
         let content;
         braced!(content in input);
         let content: proc_macro2::TokenStream = content.parse()?;
-        
-        // For test 7.
-        let mut end_int = end.base10_parse::<isize>()?;
-        if inclusive_range {
-            end_int += 1;
-        }
-
-        Ok(SeqInput {
-            ident,
-            begin: begin.base10_parse()?,
-            end: end_int,
-            content,
-        })
+
+        Ok(SeqInput { vars, zip, content })
+    }
+}
+
+// Guards against typos like `0..1000000` turning into an enormous expansion.
+// The limit applies to each invocation separately, nested ones included.
+const MAX_ITERATIONS_ENV: &str = "SEQ_MAX_ITERATIONS";
+const DEFAULT_MAX_ITERATIONS: u128 = 65536;
+
+fn max_iterations() -> syn::Result<u128> {
+    match std::env::var(MAX_ITERATIONS_ENV) {
+        Ok(limit) => limit.trim().parse().map_err(|_| {
+            let msg = format!("{} must be a non-negative integer, found `{}`", MAX_ITERATIONS_ENV, limit);
+            syn::Error::new(Span::call_site(), msg)
+        }),
+        Err(_) => Ok(DEFAULT_MAX_ITERATIONS),
+    }
+}
+
+fn peek_keyword(input: ParseStream, keyword: &str) -> bool {
+    input.cursor().ident().is_some_and(|(ident, _)| ident == keyword)
+}
+
+// A range with its modifiers: `0..8`, `(0..8)`, `(0..8).rev()`, or a list
+// `[u8, u16]`, each optionally followed by `step N`. Returns a constructor for
+// the variable that takes the range, as the name is parsed separately in
+// `(I, J) in ..`.
+fn parse_range_spec(input: ParseStream) -> syn::Result<impl FnOnce(Ident) -> SeqVar> {
+    let span = input.span();
+    let mut items = None;
+    // A parenthesized lower bound is followed by `..`, a parenthesized range
+    // by `x`, `step`, `.rev()` or the body.
+    let ((begin, end, inclusive, repr), rev) = if input.peek(token::Bracket) {
+        let list;
+        bracketed!(list in input);
+        let list = Punctuated::<ListItem, Token![,]>::parse_terminated(&list)?;
+        let list = list.into_iter().map(|item| item.0).collect::<Vec<_>>();
+        let len = list.len() as i128;
+        items = Some(list);
+        ((0, len, false, IntRepr::decimal()), false)
+    } else if input.peek(token::Paren) && !input.peek2(Token![..]) {
+        let range;
+        parenthesized!(range in input);
+        let bounds = parse_range(&range)?;
+        if !range.is_empty() {
+            return Err(range.error("expected `)` after the range"));
+        }
+        let rev = input.peek(Token![.]);
+        if rev {
+            input.parse::<Token![.]>()?;
+            let method: Ident = input.parse()?;
+            if method != "rev" {
+                return Err(syn::Error::new_spanned(method, "expected `rev`"));
+            }
+            let args;
+            parenthesized!(args in input);
+            if !args.is_empty() {
+                return Err(args.error("`rev` takes no arguments"));
+            }
+        }
+        (bounds, rev)
+    } else {
+        (parse_range(input)?, false)
+    };
+    let step = if peek_keyword(input, "step") {
+        input.parse::<Ident>()?;
+        let lit: LitInt = input.parse()?;
+        let step = lit.base10_parse::<u128>()?;
+        if step == 0 {
+            return Err(syn::Error::new_spanned(lit, "step must be greater than zero"));
+        }
+        step
+    } else {
+        1
+    };
+    Ok(move |ident: Ident| {
+        let items = items.map(|items| (items, format_ident!("{}_index", ident)));
+        SeqVar { ident, items, begin, end, inclusive, repr, rev, step, span }
+    })
+}
+
+// An item of a list, a literal like `'a'` or `-1`, or a type like `u8` or
+// `Vec<u8>`. Commas inside the type's angle brackets don't end the item.
+struct ListItem(proc_macro2::TokenStream);
+
+impl Parse for ListItem {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Lit) || input.peek(Token![-]) {
+            let negative = input.parse::<Option<Token![-]>>()?;
+            let lit: Lit = input.parse()?;
+            Ok(ListItem(quote!(#negative #lit)))
+        } else {
+            let ty: syn::Type = input.parse()?;
+            Ok(ListItem(quote!(#ty)))
+        }
+    }
+}
+
+// `begin..end` or `begin..=end`, returning whether the range is inclusive.
+fn parse_range(input: ParseStream) -> syn::Result<(i128, i128, bool, IntRepr)> {
+    let begin_span = input.span();
+    let (begin, begin_repr) = parse_bound(input)?;
+    input.parse::<Token![..]>()?;
+    // For test 7.
+    let inclusive_range = if input.peek(Token![=]) {
+        input.parse::<Token![=]>()?;
+        true
+    } else {
+        false
+    };
+    let end_span = input.span();
+    let (end, end_repr) = parse_bound(input)?;
+
+    // An empty range is fine, e.g. `0..I` in a nested invocation with I = 0,
+    // but a reversed one is most likely a mistake.
+    let exclusive_end = if inclusive_range { end.saturating_add(1) } else { end };
+    if exclusive_end < begin {
+        let msg = format!("range end {} is less than its start {}, write a descending range as `(low..high).rev()`", end, begin);
+        return Err(syn::Error::new(end_span, msg));
+    }
+    let repr = IntRepr::merge(begin_repr, end_repr)?;
+    // The end can only be negative if the start is too.
+    if let Some(suffix) = repr.suffix.as_ref().map(LitInt::suffix).filter(|s| s.starts_with('u')) {
+        if begin < 0 {
+            let msg = format!("negative bound {} doesn't fit the unsigned type `{}`", begin, suffix);
+            return Err(syn::Error::new(begin_span, msg));
+        }
+    }
+    Ok((begin, end, inclusive_range, repr))
+}
+
+// An integer bound such as `4`, `-4`, `0x1f` or `0b0100u8`, or a constant
+// expression over integer literals written in parentheses, `(4 * 2)`. Bounds
+// passed in from a `macro_rules!` metavariable arrive wrapped in an invisible
+// group and are evaluated the same way as a parenthesized expression.
+fn parse_bound(input: ParseStream) -> syn::Result<(i128, IntRepr)> {
+    if input.peek(Token![-]) {
+        let minus: Token![-] = input.parse()?;
+        if input.peek(LitInt) {
+            let lit: LitInt = input.parse()?;
+            return Ok((negate_literal(&lit)?, IntRepr::of(&lit)));
+        }
+        let (value, repr) = parse_bound(input)?;
+        let value = value.checked_neg().ok_or_else(|| syn::Error::new_spanned(minus, "range bound overflows `i128`"))?;
+        return Ok((value, repr));
+    }
+    if input.peek(token::Paren) {
+        let inner;
+        parenthesized!(inner in input);
+        return eval_int(&inner.parse()?);
+    }
+    if let Some((group, _, _)) = input.cursor().group(Delimiter::None) {
+        let tokens = group.token_stream();
+        input.step(|cursor| match cursor.group(Delimiter::None) {
+            Some((_, _, rest)) => Ok(((), rest)),
+            None => Err(cursor.error("expected a range bound")),
+        })?;
+        return eval_int(&syn::parse2(tokens)?);
+    }
+    if input.peek(Ident) {
+        let path: syn::Path = input.parse()?;
+        return eval_int(&syn::parse_quote!(#path));
+    }
+    let lit: LitInt = input.parse()?;
+    let value = lit.base10_parse::<i128>()?;
+    Ok((value, IntRepr::of(&lit)))
+}
+
+// Evaluates integer literals combined with arithmetic and bitwise operators,
+// for range bounds and `#{...}` in the body. Anything naming an item, like a
+// `const`, is rejected: its value isn't known until after the macro has
+// expanded.
+fn eval_int(expr: &Expr) -> syn::Result<(i128, IntRepr)> {
+    match expr {
+        Expr::Lit(syn::ExprLit { lit: Lit::Int(lit), .. }) => {
+            Ok((lit.base10_parse::<i128>()?, IntRepr::of(lit)))
+        }
+        Expr::Paren(e) => eval_int(&e.expr),
+        Expr::Group(e) => eval_int(&e.expr),
+        Expr::Unary(syn::ExprUnary { op: UnOp::Neg(_), expr: operand, .. }) => {
+            if let Expr::Lit(syn::ExprLit { lit: Lit::Int(lit), .. }) = &**operand {
+                return Ok((negate_literal(lit)?, IntRepr::of(lit)));
+            }
+            let (value, repr) = eval_int(operand)?;
+            let value = value.checked_neg().ok_or_else(|| syn::Error::new_spanned(expr, "value overflows `i128`"))?;
+            Ok((value, repr))
+        }
+        Expr::Binary(e) => {
+            let (lhs, lhs_repr) = eval_int(&e.left)?;
+            let (rhs, rhs_repr) = eval_int(&e.right)?;
+            let value = match e.op {
+                BinOp::Add(_) => lhs.checked_add(rhs),
+                BinOp::Sub(_) => lhs.checked_sub(rhs),
+                BinOp::Mul(_) => lhs.checked_mul(rhs),
+                BinOp::Div(_) => lhs.checked_div(rhs),
+                BinOp::Rem(_) => lhs.checked_rem(rhs),
+                BinOp::Shl(_) => u32::try_from(rhs).ok().and_then(|rhs| lhs.checked_shl(rhs)),
+                BinOp::Shr(_) => u32::try_from(rhs).ok().and_then(|rhs| lhs.checked_shr(rhs)),
+                BinOp::BitAnd(_) => Some(lhs & rhs),
+                BinOp::BitOr(_) => Some(lhs | rhs),
+                BinOp::BitXor(_) => Some(lhs ^ rhs),
+                _ => return Err(syn::Error::new_spanned(e.op, "unsupported operator in constant expression")),
+            };
+            let value = value.ok_or_else(|| syn::Error::new_spanned(expr, "value overflows `i128` or divides by zero"))?;
+            Ok((value, IntRepr::merge(lhs_repr, rhs_repr)?))
+        }
+        Expr::Path(e) => Err(syn::Error::new_spanned(
+            e,
+            format!(
+                "cannot evaluate `{}` while expanding `seq!`, only integer literals, loop variables and arithmetic on them are supported",
+                quote!(#e).to_string().replace(' ', ""),
+            ),
+        )),
+        _ => Err(syn::Error::new_spanned(expr, "expected an integer literal or constant expression")),
+    }
+}
+
+// `-lit`, which unlike `-(lit)` reaches down to `i128::MIN`.
+fn negate_literal(lit: &LitInt) -> syn::Result<i128> {
+    let magnitude = lit.base10_parse::<u128>()?;
+    0i128
+        .checked_sub_unsigned(magnitude)
+        .ok_or_else(|| syn::Error::new_spanned(lit, "value overflows `i128`"))
+}
+
+// Evaluates the condition of an `#if`: comparisons of integer expressions
+// combined with `&&`, `||` and `!`.
+fn eval_bool(expr: &Expr) -> syn::Result<bool> {
+    match expr {
+        Expr::Lit(syn::ExprLit { lit: Lit::Bool(lit), .. }) => Ok(lit.value),
+        Expr::Paren(e) => eval_bool(&e.expr),
+        Expr::Group(e) => eval_bool(&e.expr),
+        Expr::Unary(syn::ExprUnary { op: UnOp::Not(_), expr, .. }) => Ok(!eval_bool(expr)?),
+        Expr::Binary(e) => match e.op {
+            BinOp::And(_) => Ok(eval_bool(&e.left)? && eval_bool(&e.right)?),
+            BinOp::Or(_) => Ok(eval_bool(&e.left)? || eval_bool(&e.right)?),
+            BinOp::Eq(_) | BinOp::Ne(_) | BinOp::Lt(_) | BinOp::Le(_) | BinOp::Gt(_) | BinOp::Ge(_) => {
+                let (lhs, _) = eval_int(&e.left)?;
+                let (rhs, _) = eval_int(&e.right)?;
+                Ok(match e.op {
+                    BinOp::Eq(_) => lhs == rhs,
+                    BinOp::Ne(_) => lhs != rhs,
+                    BinOp::Lt(_) => lhs < rhs,
+                    BinOp::Le(_) => lhs <= rhs,
+                    BinOp::Gt(_) => lhs > rhs,
+                    _ => lhs >= rhs,
+                })
+            }
+            _ => Err(syn::Error::new_spanned(expr, "expected a comparison, `&&`, `||` or `!`")),
+        },
+        _ => Err(syn::Error::new_spanned(expr, "expected a condition like `N % 2 == 0`")),
+    }
+}
+
+// Splits the `:0W` off the end of `~{N:0W}`, giving the variable and the
+// zero-padded width `W`, or 0 if there is none. It is only recognized inside
+// the braces, where it can't be confused with Rust code such as the field init
+// in `Foo { f~N: 05 }`.
+fn pad_width(tokens: &[TokenTree]) -> (&[TokenTree], usize) {
+    if let [expr @ .., TokenTree::Punct(p), TokenTree::Literal(lit)] = tokens {
+        let repr = lit.to_string();
+        if let (':', Some(Ok(width))) = (p.as_char(), repr.strip_prefix('0').map(str::parse::<usize>)) {
+            if width > 0 {
+                return (expr, width);
+            }
+        }
+    }
+    (tokens, 0)
+}
+
+// Whether the group following `tokens` holds the arguments of a `seq!` call.
+fn is_nested_seq(tokens: &[TokenTree]) -> bool {
+    match tokens {
+        [.., TokenTree::Ident(name), TokenTree::Punct(bang)] => name == "seq" && bang.as_char() == '!',
+        _ => false,
+    }
+}
+
+// The end of a repetition after `#(...)`: a `*`, optionally preceded by a
+// separator as in `macro_rules!`, e.g. `,*`, `+*` or `=>*`. Returns the
+// separator, possibly empty, and the number of tokens up to and including
+// the `*`.
+fn repeat_separator(tokens: &[TokenTree]) -> Option<(proc_macro2::TokenStream, usize)> {
+    let mut separator = Vec::new();
+    for token in tokens {
+        match token {
+            TokenTree::Punct(p) if p.as_char() == '*' => {
+                let len = separator.len() + 1;
+                // The separator was joined to the `*`, not to whatever follows it now.
+                if let Some(TokenTree::Punct(last)) = separator.last_mut() {
+                    let mut alone = Punct::new(last.as_char(), Spacing::Alone);
+                    alone.set_span(last.span());
+                    *last = alone;
+                }
+                return Some((separator.into_iter().collect(), len));
+            }
+            // A multi-character operator like `=>`, or one whose last
+            // character isn't joined to the `*` as in `, *`.
+            TokenTree::Punct(p) if separator.iter().all(|t| matches!(t, TokenTree::Punct(p) if p.spacing() == Spacing::Joint)) => {
+                separator.push(token.clone());
+            }
+            // An identifier or literal separator, like `#(...) and *`.
+            TokenTree::Ident(_) | TokenTree::Literal(_) if separator.is_empty() => separator.push(token.clone()),
+            _ => return None,
+        }
+    }
+    None
+}
+
+// Whether any of `vars` appears in `ts`.
+fn mentions(ts: &proc_macro2::TokenStream, vars: &[Ident]) -> bool {
+    ts.clone().into_iter().any(|t| match t {
+        TokenTree::Ident(i) => vars.contains(&i),
+        TokenTree::Group(g) => mentions(&g.stream(), vars),
+        _ => false,
+    })
+}
+
+// Replaces `~var` and `~{var:0W}` in `s` with `text(W)`, or returns `None`
+// if `s` doesn't mention the variable.
+fn paste_str_var(s: &str, var: &Ident, text: impl Fn(usize) -> String) -> Option<String> {
+    let plain = format!("~{}", var);
+    let padded = format!("~{{{}:0", var);
+    let mut out = String::new();
+    let mut pasted = false;
+    let mut rest = s;
+    while let Some(at) = rest.find('~') {
+        out += &rest[..at];
+        rest = &rest[at..];
+        if let Some(after) = rest.strip_prefix(&padded) {
+            let digits = &after[..after.find(|c: char| !c.is_ascii_digit()).unwrap_or(after.len())];
+            match (digits.parse::<usize>(), after[digits.len()..].strip_prefix('}')) {
+                (Ok(width), Some(after)) if width > 0 => {
+                    out += &text(width);
+                    rest = after;
+                    pasted = true;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        match rest.strip_prefix(&plain) {
+            Some(after) if !after.starts_with(|c: char| c.is_alphanumeric() || c == '_') => {
+                out += &text(0);
+                rest = after;
+                pasted = true;
+            }
+            _ => {
+                out.push('~');
+                rest = &rest[1..];
+            }
+        }
+    }
+    if !pasted {
+        return None;
+    }
+    out += rest;
+    Some(out)
+}
+
+// A number as pasted into an identifier, zero-padded to `width`.
+fn number_text(n: i128, width: usize) -> Result<String, String> {
+    if n < 0 {
+        return Err(format!("cannot paste negative value {} into an identifier", n));
+    }
+    Ok(format!("{:0width$}", n, width = width))
+}
+
+// The text of a list item like `Vec<u8>`, `&'a [u8; 4]` or `-20` as written
+// in Rust code, without the spacing that `TokenStream::to_string` adds.
+fn item_text(tokens: &proc_macro2::TokenStream) -> String {
+    let mut out = String::new();
+    let mut op = String::new();
+    let mut after_word = false;
+    for token in tokens.clone() {
+        match token {
+            TokenTree::Punct(p) => {
+                op.push(p.as_char());
+                if p.spacing() == Spacing::Joint {
+                    continue;
+                }
+                match op.as_str() {
+                    "," | ";" => out += &format!("{} ", op),
+                    "->" | "+" | "=" => out += &format!(" {} ", op),
+                    _ => out += &op,
+                }
+                op.clear();
+                after_word = false;
+            }
+            TokenTree::Group(g) => {
+                let (open, close) = match g.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::None => ("", ""),
+                };
+                // `&'a [u8]`, but `fn(u8)`.
+                if after_word && g.delimiter() != Delimiter::Parenthesis {
+                    out.push(' ');
+                }
+                out += &format!("{}{}{}", open, item_text(&g.stream()), close);
+                after_word = true;
+            }
+            TokenTree::Ident(_) | TokenTree::Literal(_) => {
+                // A lifetime's `'` is joined to its name.
+                out += &op;
+                op.clear();
+                if after_word {
+                    out.push(' ');
+                }
+                out += &token.to_string();
+                after_word = true;
+            }
+        }
+    }
+    out
+}
+
+// Whether `s` can be made into an `Ident` without panicking. Rust uses the
+// Unicode XID classes, which are narrower than `char::is_alphanumeric`: `²`
+// is numeric but can't be part of an identifier.
+fn is_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if unicode_ident::is_xid_start(c) || c == '_')
+        && chars.all(unicode_ident::is_xid_continue)
+        && s != "_"
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Radix {
+    Decimal,
+    Hex,
+    Octal,
+    Binary,
+}
+
+// Radix and suffix of the range bounds. Literals substituted for the loop
+// variable are written the same way, e.g. `0x00u8..0x10u8` emits `0x0au8`.
+struct IntRepr {
+    radix: Radix,
+    suffix: Option<LitInt>,
+}
+
+impl IntRepr {
+    fn of(lit: &LitInt) -> Self {
+        let repr = lit.to_string();
+        let radix = match repr.get(..2) {
+            Some("0x") => Radix::Hex,
+            Some("0o") => Radix::Octal,
+            Some("0b") => Radix::Binary,
+            _ => Radix::Decimal,
+        };
+        let suffix = if lit.suffix().is_empty() { None } else { Some(lit.clone()) };
+        IntRepr { radix, suffix }
+    }
+
+    // Bounds have to agree on the suffix, if both have one. A radix other than
+    // decimal on either bound is used for the output.
+    fn merge(begin: Self, end: Self) -> syn::Result<Self> {
+        let suffix = match (begin.suffix, end.suffix) {
+            (Some(b), Some(e)) if b.suffix() != e.suffix() => {
+                return Err(syn::Error::new_spanned(&e, format!("expected suffix `{}` to match `{}`", b.suffix(), b)));
+            }
+            (b, e) => b.or(e),
+        };
+        let radix = if begin.radix == Radix::Decimal { end.radix } else { begin.radix };
+        Ok(IntRepr { radix, suffix })
+    }
+
+    fn decimal() -> Self {
+        IntRepr { radix: Radix::Decimal, suffix: None }
+    }
+
+    fn literal(&self, n: i128, span: Span) -> proc_macro2::TokenStream {
+        let digits = match self.radix {
+            Radix::Decimal => format!("{}", n.unsigned_abs()),
+            Radix::Hex => format!("{:#x}", n.unsigned_abs()),
+            Radix::Octal => format!("{:#o}", n.unsigned_abs()),
+            Radix::Binary => format!("{:#b}", n.unsigned_abs()),
+        };
+        let suffix = self.suffix.as_ref().map(|lit| lit.suffix()).unwrap_or("");
+        let lit = LitInt::new(&format!("{}{}", digits, suffix), span);
+        if n < 0 {
+            parenthesize(quote_spanned!(span=> -#lit), span)
+        } else {
+            quote!(#lit)
+        }
+    }
+}
+
+// A negative number is substituted in parentheses so that `N.abs()` applies to
+// all of it. A single `-2` literal token won't do, rustc splits it back into
+// `-` and `2`, and it ignores invisible groups.
+fn parenthesize(tokens: proc_macro2::TokenStream, span: Span) -> proc_macro2::TokenStream {
+    let mut group = proc_macro2::Group::new(Delimiter::Parenthesis, tokens);
+    group.set_span(span);
+    quote!(#group)
+}
+
+// Where a loop variable appears in the body: as itself, or for a list, as
+// `T_index`. Holds the position in `SeqInput::vars`.
+#[derive(Clone, Copy)]
+enum Occurrence {
+    Value(usize),
+    Index(usize),
+}
+
+impl SeqVar {
+    // The tokens substituted for the variable when it has value `n`.
+    fn tokens(&self, n: i128, span: Span) -> proc_macro2::TokenStream {
+        match &self.items {
+            Some((items, _)) => {
+                let item = &items[n as usize];
+                match item.clone().into_iter().next() {
+                    Some(TokenTree::Punct(p)) if p.as_char() == '-' => parenthesize(item.clone(), span),
+                    _ => item.clone(),
+                }
+            }
+            None => self.repr.literal(n, span),
+        }
+    }
+
+    // The text pasted into identifiers and strings for value `n`, padded with
+    // zeros to `width` if it is a number. Fails with the reason if the value
+    // can't be part of an identifier.
+    fn text(&self, n: i128, width: usize) -> Result<String, String> {
+        let item = match &self.items {
+            Some((items, _)) => &items[n as usize],
+            None => return number_text(n, width),
+        };
+        if let Ok(ident) = syn::parse2::<Ident>(item.clone()) {
+            return Ok(ident.to_string());
+        }
+        match syn::parse2::<Lit>(item.clone()) {
+            Ok(Lit::Int(lit)) => lit.base10_parse::<i128>().map_err(|e| e.to_string()).and_then(|n| number_text(n, width)),
+            Ok(Lit::Str(lit)) => Ok(lit.value()),
+            Ok(Lit::Char(lit)) => Ok(lit.value().to_string()),
+            Ok(Lit::Bool(lit)) => Ok(lit.value.to_string()),
+            _ => Err(format!("cannot paste `{}` into an identifier", item_text(item))),
+        }
+    }
+
+    // The number of values, without producing them.
+    fn len(&self) -> u128 {
+        // `5..=4` is the only way to get here with `end < begin`.
+        if self.end < self.begin {
+            return 0;
+        }
+        let span = self.end.abs_diff(self.begin);
+        let span = if self.inclusive { span.saturating_add(1) } else { span };
+        span / self.step + u128::from(span % self.step != 0)
+    }
+
+    // The values of the loop variable, in iteration order.
+    fn values(&self) -> Vec<i128> {
+        let last = if self.inclusive { self.end } else { self.end.wrapping_sub(1) };
+        (0..self.len())
+            .map(|k| {
+                // Stays within the range, so it can't actually wrap.
+                let offset = k * self.step;
+                if self.rev {
+                    last.wrapping_sub_unsigned(offset)
+                } else {
+                    self.begin.wrapping_add_unsigned(offset)
+                }
+            })
+            .collect()
     }
 }
 
 impl SeqInput {
-    fn expand(&self, ts: &proc_macro2::TokenStream, n: isize) -> proc_macro2::TokenStream {
+    // The values of all variables, one entry per iteration, in the order of
+    // `self.vars`.
+    fn iterations(&self) -> Vec<Vec<i128>> {
+        let values = self.vars.iter().map(SeqVar::values).collect::<Vec<_>>();
+        if self.zip {
+            (0..values[0].len())
+                .map(|i| values.iter().map(|v| v[i]).collect())
+                .collect()
+        } else {
+            values.iter().fold(vec![Vec::new()], |acc, v| {
+                acc.iter()
+                    .flat_map(|prefix| v.iter().map(move |&n| prefix.iter().copied().chain(Some(n)).collect()))
+                    .collect()
+            })
+        }
+    }
+
+    // The variable `ident` refers to, unless a nested invocation holds it back.
+    fn var(&self, ident: &Ident, inner: &[Ident]) -> Option<Occurrence> {
+        if inner.contains(ident) {
+            return None;
+        }
+        self.occurrences().find(|(name, _)| *name == ident).map(|(_, occurrence)| occurrence)
+    }
+
+    fn occurrences(&self) -> impl Iterator<Item = (&Ident, Occurrence)> {
+        self.vars.iter().enumerate().flat_map(|(v, var)| {
+            let index = var.items.as_ref().map(|(_, index)| (index, Occurrence::Index(v)));
+            Some((&var.ident, Occurrence::Value(v))).into_iter().chain(index)
+        })
+    }
+
+    // The tokens for an occurrence of a variable at `span` in the body.
+    fn occurrence_tokens(&self, occurrence: Occurrence, n: &[i128], span: Span) -> proc_macro2::TokenStream {
+        match occurrence {
+            Occurrence::Value(v) => self.vars[v].tokens(n[v], span),
+            Occurrence::Index(v) => IntRepr::decimal().literal(n[v], span),
+        }
+    }
+
+    fn occurrence_text(&self, occurrence: Occurrence, n: &[i128], width: usize) -> Result<String, String> {
+        match occurrence {
+            Occurrence::Value(v) => self.vars[v].text(n[v], width),
+            Occurrence::Index(v) => number_text(n[v], width),
+        }
+    }
+
+    fn expand(&self, ts: &proc_macro2::TokenStream, n: &[i128]) -> syn::Result<proc_macro2::TokenStream> {
+        self.expand_nested(ts, n, &[])
+    }
+
+    // `inner` holds the variables of the nested `seq!` invocations `ts` is
+    // part of. Pasting stops in front of those, so that `m~I~_~J` inside
+    // `seq!(J in ..)` becomes `m2_~J` here and `m2_3` in the nested expansion.
+    fn expand_nested(&self, ts: &proc_macro2::TokenStream, n: &[i128], inner: &[Ident]) -> syn::Result<proc_macro2::TokenStream> {
         let tokens_iter = ts.clone().into_iter();
         let tokens = tokens_iter.collect::<Vec<_>>();
         let mut r = proc_macro2::TokenStream::new();
@@ -59,126 +739,383 @@ impl SeqInput {
         let mut index = 0usize;
         while index < tokens.len() {
             let t0 = tokens.get(index).unwrap();
-            let t1 = tokens.get(index + 1);
-            let t2 = tokens.get(index + 2);
-            // eprintln!("token: {}", t0);
             match &t0 {
                 proc_macro2::TokenTree::Group(g) => {
-                    let inner = self.expand(&g.stream(), n);
-                    let mut inner_g = proc_macro2::Group::new(g.delimiter(), inner);
+                    let stream = if is_nested_seq(&tokens[..index]) {
+                        self.expand_nested_seq(&g.stream(), n, inner)?
+                    } else {
+                        self.expand_nested(&g.stream(), n, inner)?
+                    };
+                    let mut inner_g = proc_macro2::Group::new(g.delimiter(), stream);
                     inner_g.set_span(g.clone().span());
                     r.extend(quote!{#inner_g});
                 },
                 proc_macro2::TokenTree::Ident(i) => {
-                    if i == &self.ident {
-                        let lit = proc_macro2::Literal::i64_unsuffixed(n as i64);
-                        r.extend(quote!{#lit});
+                    if let Some((ident, len)) = self.match_paste_ident(n, &tokens[index..], inner)? {
+                        r.extend(quote!{#ident});
+                        index += len - 1;
+                    } else if let Some(occurrence) = self.var(i, inner) {
+                        r.extend(self.occurrence_tokens(occurrence, n, i.span()));
                     } else {
-                        if let Some(ts) = self.match_paste_ident(n, t0, t1, t2) {
-                            r.extend(quote!{#ts});
-                            index += 2;
-                        } else {
-                            r.extend(quote!{#t0});
-                        }   
+                        r.extend(quote!{#t0});
+                    }
+                },
+                proc_macro2::TokenTree::Literal(lit) => {
+                    r.extend(self.paste_str(lit, n, inner));
+                },
+                proc_macro2::TokenTree::Punct(p) if p.as_char() == '#' => {
+                    match tokens.get(index + 1) {
+                        Some(TokenTree::Ident(kw)) if kw == "if" => {
+                            let (output, len) = self.expand_if(&tokens[index..], n, inner)?;
+                            r.extend(output);
+                            index += len - 1;
+                        }
+                        Some(TokenTree::Ident(kw)) if kw == "else" => {
+                            return Err(syn::Error::new_spanned(quote!(#t0 #kw), "`#else` without a preceding `#if`"));
+                        }
+                        // Inside the body of a nested invocation, they are that one's.
+                        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis && inner.is_empty() => {
+                            return Err(syn::Error::new_spanned(
+                                quote!(#t0 #g),
+                                "repeated sections can't be nested, use a nested `seq!` instead",
+                            ));
+                        }
+                        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => {
+                            match self.eval_inline(g, n, inner)? {
+                                Ok((value, repr)) => r.extend(repr.literal(value, g.span())),
+                                Err(held) => r.extend(quote!{#t0 #held}),
+                            }
+                            index += 1;
+                        }
+                        _ => r.extend(quote!{#t0}),
                     }
                 },
                 _ => { r.extend(quote!{#t0}) },
             }
             index += 1;
         }
-        r
+        Ok(r)
     }
 
-    fn match_paste_ident(&self, n: isize, t0: &TokenTree, t1: Option<&TokenTree>, t2: Option<&TokenTree>) -> Option<proc_macro2::TokenStream> {
-        let t1 = t1?;
-        let t2 = t2?;
-        if &t1.to_string() == "~" {
-            if let proc_macro2::TokenTree::Ident(i) = t2 {
-                if i == &self.ident {
-                    eprintln!("--- t1 & t2 matched");
-                    let token_string = format!("{}{}", t0.to_string(), n);
-                    let new_ident = proc_macro2::Ident::new(&token_string, t0.span());
-                    let r = quote!{#new_ident};
-                    return Some(r);
+    // Pastes a chain of identifiers joined by `~`, e.g. `Reg~N~Mask`, into a
+    // single identifier with the span of the first one. Loop variables can be
+    // zero-padded to a width with `~{N:02}`. Returns the identifier and the
+    // number of tokens it replaces.
+    fn match_paste_ident(&self, n: &[i128], tokens: &[TokenTree], inner: &[Ident]) -> syn::Result<Option<(Ident, usize)>> {
+        let mut name = String::new();
+        let mut failure = None;
+        let mut pieces = 0;
+        let mut index = 0;
+        loop {
+            // `~{N:02}` pads the variable inside the braces with zeros.
+            let (piece, width) = match &tokens[index..] {
+                [TokenTree::Group(g), ..] => {
+                    let inside = g.stream().into_iter().collect::<Vec<_>>();
+                    index += 1;
+                    match pad_width(&inside) {
+                        (piece @ ([TokenTree::Ident(_)] | [TokenTree::Punct(_), TokenTree::Group(_)]), width) if width > 0 => {
+                            (piece.to_vec(), width)
+                        }
+                        _ => return Err(syn::Error::new(g.span(), "expected a loop variable and a width, like `~{N:02}`")),
+                    }
+                }
+                [hash @ TokenTree::Punct(_), group @ TokenTree::Group(_), ..] => {
+                    index += 2;
+                    (vec![hash.clone(), group.clone()], 0)
                 }
+                [ident, ..] => {
+                    index += 1;
+                    (vec![ident.clone()], 0)
+                }
+                [] => unreachable!("paste chains start with and continue at an identifier"),
+            };
+            pieces += 1;
+            match piece.as_slice() {
+                // `#{N + 1}` pastes like a loop variable.
+                [TokenTree::Punct(hash), TokenTree::Group(g)] if hash.as_char() == '#' => {
+                    let (value, _) = match self.eval_inline(g, n, inner)? {
+                        Ok(value) => value,
+                        Err(_) => unreachable!("`is_paste` stops in front of held back variables"),
+                    };
+                    match number_text(value, width) {
+                        Ok(text) => name += &text,
+                        Err(msg) => {
+                            let span = hash.span().join(g.span()).unwrap_or_else(|| g.span());
+                            return Err(syn::Error::new(span, msg));
+                        }
+                    }
+                }
+                [TokenTree::Ident(piece)] => {
+                    if let Some(occurrence) = self.var(piece, inner) {
+                        match self.occurrence_text(occurrence, n, width) {
+                            Ok(text) => name += &text,
+                            Err(msg) => failure = failure.or(Some((piece.clone(), msg))),
+                        }
+                    } else if width > 0 {
+                        return Err(syn::Error::new_spanned(piece, format!("`{}` is not a loop variable, only those can be padded", piece)));
+                    } else if pieces == 1 && inner.contains(piece) {
+                        return Ok(None);
+                    } else {
+                        name += &piece.to_string();
+                    }
+                }
+                _ => unreachable!("paste chains start with and continue at an identifier"),
+            }
+            if self.is_paste(&tokens[index..], inner) {
+                index += 1;
+            } else {
+                break;
+            }
+        }
+        if pieces == 1 {
+            return Ok(None);
+        }
+        if let Some((var, msg)) = failure {
+            return Err(syn::Error::new_spanned(var, msg));
+        }
+        let span = tokens[0].span();
+        if !is_ident(&name) {
+            return Err(syn::Error::new(span, format!("`{}` is not a valid identifier", name)));
+        }
+        Ok(Some((Ident::new(&name, span), index)))
+    }
+
+    // Whether `tokens` continue a paste chain with `~` and an identifier that
+    // isn't held back for a nested invocation, or with `~#{...}` or `~{N:02}`
+    // that doesn't mention one.
+    fn is_paste(&self, tokens: &[TokenTree], inner: &[Ident]) -> bool {
+        match tokens {
+            [TokenTree::Punct(p), TokenTree::Ident(next), ..] => p.as_char() == '~' && !inner.contains(next),
+            [TokenTree::Punct(p), TokenTree::Punct(hash), TokenTree::Group(g), ..] => {
+                p.as_char() == '~'
+                    && hash.as_char() == '#'
+                    && g.delimiter() == Delimiter::Brace
+                    && !mentions(&g.stream(), inner)
             }
+            [TokenTree::Punct(p), TokenTree::Group(g), ..] => {
+                p.as_char() == '~' && g.delimiter() == Delimiter::Brace && !mentions(&g.stream(), inner)
+            }
+            _ => false,
         }
-        None
+    }
+
+    // `#if (cond) { .. }`, optionally followed by `#else { .. }` or
+    // `#else #if ..`, starting at the `#`. Emits the contents of the branch
+    // whose condition holds. Returns the output and the number of tokens the
+    // conditional spans.
+    fn expand_if(&self, tokens: &[TokenTree], n: &[i128], inner: &[Ident]) -> syn::Result<(proc_macro2::TokenStream, usize)> {
+        let (cond, then) = match &tokens[2..] {
+            [TokenTree::Group(cond), TokenTree::Group(then), ..]
+                if cond.delimiter() == Delimiter::Parenthesis && then.delimiter() == Delimiter::Brace => (cond, then),
+            _ => {
+                let hash_if = &tokens[..2];
+                return Err(syn::Error::new_spanned(quote!(#(#hash_if)*), "expected `(condition) { ... }` after `#if`"));
+            }
+        };
+        let mut len = 4;
+        let otherwise = match &tokens[len..] {
+            [TokenTree::Punct(hash), TokenTree::Ident(kw), rest @ ..] if hash.as_char() == '#' && kw == "else" => {
+                match rest {
+                    [TokenTree::Group(g), ..] if g.delimiter() == Delimiter::Brace => {
+                        len += 3;
+                        Some(self.expand_nested(&g.stream(), n, inner)?)
+                    }
+                    [TokenTree::Punct(hash), TokenTree::Ident(kw), ..] if hash.as_char() == '#' && kw == "if" => {
+                        let (output, else_len) = self.expand_if(&tokens[len + 2..], n, inner)?;
+                        len += 2 + else_len;
+                        Some(output)
+                    }
+                    _ => {
+                        return Err(syn::Error::new_spanned(quote!(#hash #kw), "expected `{ ... }` or `#if` after `#else`"));
+                    }
+                }
+            }
+            _ => None,
+        };
+        let then = self.expand_nested(&then.stream(), n, inner)?;
+
+        let condition = self.expand_nested(&cond.stream(), n, inner)?;
+        if mentions(&condition, inner) {
+            // Decided by the nested invocation holding back the variable.
+            let hash_if = &tokens[..2];
+            let mut condition = proc_macro2::Group::new(Delimiter::Parenthesis, condition);
+            condition.set_span(cond.span());
+            let mut r = quote!(#(#hash_if)* #condition { #then });
+            if let Some(otherwise) = otherwise {
+                let hash_else = &tokens[4..6];
+                r.extend(quote!(#(#hash_else)* { #otherwise }));
+            }
+            return Ok((r, len));
+        }
+        let expr = syn::parse2::<Expr>(condition).map_err(|e| syn::Error::new(cond.span(), e))?;
+        // Point at the condition as a whole rather than at the first operand.
+        let holds = eval_bool(&expr).map_err(|e| syn::Error::new(cond.span(), e))?;
+        let output = if holds { then } else { otherwise.unwrap_or_default() };
+        Ok((output, len))
+    }
+
+    // Evaluates the expression in `#{...}` with the current values of the loop
+    // variables. If it mentions a variable held back for a nested invocation,
+    // the group is returned with the other variables substituted instead.
+    fn eval_inline(&self, g: &proc_macro2::Group, n: &[i128], inner: &[Ident]) -> syn::Result<Result<(i128, IntRepr), proc_macro2::Group>> {
+        let stream = self.expand_nested(&g.stream(), n, inner)?;
+        if mentions(&stream, inner) {
+            let mut held = proc_macro2::Group::new(Delimiter::Brace, stream);
+            held.set_span(g.span());
+            return Ok(Err(held));
+        }
+        let expr = syn::parse2::<Expr>(stream).map_err(|e| syn::Error::new(g.span(), e))?;
+        eval_int(&expr).map(Ok)
+    }
+
+    // The arguments of a nested `seq!(J in 0..I { .. })`. The ranges may refer
+    // to the variables being expanded here and are substituted first. The body
+    // is expanded with `J` held back for the nested invocation, which also
+    // makes `J` shadow a variable of the same name here.
+    fn expand_nested_seq(&self, ts: &proc_macro2::TokenStream, n: &[i128], inner: &[Ident]) -> syn::Result<proc_macro2::TokenStream> {
+        let mut tokens = ts.clone().into_iter().collect::<Vec<_>>();
+        let body = match tokens.last() {
+            Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => tokens.pop(),
+            _ => None,
+        };
+        let mut r = proc_macro2::TokenStream::new();
+        let mut vars = inner.to_vec();
+        for (i, token) in tokens.iter().enumerate() {
+            let next_is_in = matches!(tokens.get(i + 1), Some(TokenTree::Ident(next)) if next == "in");
+            match token {
+                // The names in `(I, J) in ..` and `I in ..`.
+                TokenTree::Group(g) if i == 0 && g.delimiter() == Delimiter::Parenthesis => {
+                    for t in g.stream() {
+                        if let TokenTree::Ident(var) = t {
+                            vars.push(format_ident!("{}_index", var));
+                            vars.push(var);
+                        }
+                    }
+                    r.extend(quote!(#token));
+                }
+                TokenTree::Ident(var) if next_is_in => {
+                    vars.push(var.clone());
+                    vars.push(format_ident!("{}_index", var));
+                    r.extend(quote!(#token));
+                }
+                _ => r.extend(self.expand_nested(&quote!(#token), n, inner)?),
+            }
+        }
+        if let Some(TokenTree::Group(g)) = body {
+            let mut body = proc_macro2::Group::new(Delimiter::Brace, self.expand_nested(&g.stream(), n, &vars)?);
+            body.set_span(g.span());
+            r.extend(quote!(#body));
+        }
+        Ok(r)
+    }
+
+    // Replaces `~N` and `~{N:02}` inside a string literal, so `"field_~N"`
+    // becomes `"field_3"`. Other literals are returned unchanged.
+    fn paste_str(&self, lit: &proc_macro2::Literal, n: &[i128], inner: &[Ident]) -> proc_macro2::TokenStream {
+        let mut s = match syn::parse2::<syn::LitStr>(quote!(#lit)) {
+            Ok(s) => s.value(),
+            Err(_) => return quote!(#lit),
+        };
+        let mut pasted = false;
+        for (name, occurrence) in self.occurrences() {
+            if inner.contains(name) {
+                continue;
+            }
+            // Unlike identifiers, strings can hold negative numbers and types.
+            let text = |width| match occurrence {
+                Occurrence::Value(v) => match &self.vars[v].items {
+                    Some((items, _)) => self.vars[v]
+                        .text(n[v], width)
+                        .unwrap_or_else(|_| item_text(&items[n[v] as usize])),
+                    None => format!("{:0width$}", n[v], width = width),
+                },
+                Occurrence::Index(v) => format!("{:0width$}", n[v], width = width),
+            };
+            if let Some(out) = paste_str_var(&s, name, text) {
+                s = out;
+                pasted = true;
+            }
+        }
+        if !pasted {
+            return quote!(#lit);
+        }
+        let pasted = syn::LitStr::new(&s, lit.span());
+        quote!(#pasted)
     }
 
     // For test 05.
-    fn expand_repeat(&self) -> Option<proc_macro2::TokenStream> {
-        let tb = TokenBuffer::new2(self.content.clone());
-        let cursor = tb.begin();
+    fn expand_repeat(&self) -> syn::Result<Option<proc_macro2::TokenStream>> {
         let mut matched = false;
-        let output = self.expand_repeat_impl(cursor, &mut matched);
-        // eprintln!("final match: {}", matched);
-        // eprintln!("cursor output: {}", output);
-        if matched {
-            Some(output)
-        } else {
-            None
+        let mut conditional = None;
+        let output = self.expand_repeat_impl(&self.content, &mut matched, &mut conditional)?;
+        if !matched {
+            return Ok(None);
+        }
+        // Outside the sections there is no value to test the condition on.
+        if let Some(hash_kw) = conditional {
+            return Err(syn::Error::new_spanned(&hash_kw, format!(
+                "`{}` outside a repeated section, move it into the `#(...)*`",
+                hash_kw.to_string().replace(' ', ""),
+            )));
         }
+        Ok(Some(output))
     }
 
-    fn expand_repeat_impl(&self, cursor: Cursor, matched: &mut bool) -> proc_macro2::TokenStream {
-        let mut c = cursor;
+    // Works on token trees rather than a `syn` cursor, which would look
+    // through the invisible groups around `macro_rules!` fragments. Groups are
+    // rebuilt with their original delimiter and span.
+    // The first `#if` or `#else` met outside a section goes in `conditional`.
+    fn expand_repeat_impl(&self, ts: &proc_macro2::TokenStream, matched: &mut bool,
+                          conditional: &mut Option<proc_macro2::TokenStream>) -> syn::Result<proc_macro2::TokenStream> {
+        let tokens = ts.clone().into_iter().collect::<Vec<_>>();
         let mut output = proc_macro2::TokenStream::new();
-        while !c.eof() {
-            if let Some((punct, cursor)) = c.punct() {
-                if punct.as_char() == '#' {
-                    if let Some((c1, _, c2)) = cursor.group(Delimiter::Parenthesis) {
-                        if let Some((punct, cursor)) = c2.punct() {
-                            if punct.as_char() == '*' {
-                                eprintln!("match repeated: {}", c1.token_stream());
-                                for n in self.begin..self.end {
-                                    output.extend(self.expand(&c1.token_stream(), n));
-                                }
-                                c = cursor;
-                                *matched = true;
-                                continue;
-                            }
+        let mut index = 0;
+        while index < tokens.len() {
+            match &tokens[index..] {
+                [TokenTree::Punct(hash), TokenTree::Group(section), rest @ ..]
+                    if hash.as_char() == '#' && section.delimiter() == Delimiter::Parenthesis =>
+                {
+                    let (separator, len) = repeat_separator(rest).ok_or_else(|| {
+                        syn::Error::new(
+                            section.span(),
+                            "expected `*` after the repeated section, optionally with a separator in between like `,*`",
+                        )
+                    })?;
+                    for (i, n) in self.iterations().iter().enumerate() {
+                        if i > 0 {
+                            output.extend(separator.clone());
                         }
+                        output.extend(self.expand(&section.stream(), n)?);
                     }
+                    *matched = true;
+                    index += 2 + len;
                 }
-            }
-
-            if let Some((c1, _, c2)) = c.group(Delimiter::Brace) {
-                // println!("Cursor: {{}}");
-                let ts = self.expand_repeat_impl(c1, matched);
-                output.extend(quote!{{#ts}});
-                c = c2;
-            } else if let Some((c1, _, c2)) = c.group(Delimiter::Bracket) {
-                // println!("Cursor: []");
-                let ts = self.expand_repeat_impl(c1, matched);
-                output.extend(quote!{[#ts]});
-                c = c2;
-            } else if let Some((c1, _, c2)) = c.group(Delimiter::Parenthesis) {
-                // println!("Cursor: ()");
-                let ts = self.expand_repeat_impl(c1, matched);
-                output.extend(quote!{(#ts)});
-                c = c2;
-            } else if let Some((punct, cursor)) = c.punct() {
-                // println!("Cursor: {}", punct);
-                output.extend(quote!{#punct});
-                c = cursor;
-            } else if let Some((ident, cursor)) = c.ident() {
-                // println!("Cursor: {}", ident);
-                output.extend(quote!{#ident});
-                c = cursor;
-            } else if let Some((literal, cursor)) = c.literal() {
-                // println!("Cursor: {}", literal);
-                output.extend(quote!{#literal});
-                c = cursor;
-            } else if let Some((lifetime, cursor)) = c.lifetime() {
-                // println!("Cursor: {}", lifetime);
-                output.extend(quote!{#lifetime});
-                c = cursor;
-            } else {
-                panic!("Unavailable tokens");
+                [TokenTree::Punct(hash), TokenTree::Ident(kw), ..]
+                    if hash.as_char() == '#' && (kw == "if" || kw == "else") =>
+                {
+                    conditional.get_or_insert_with(|| quote!(#hash #kw));
+                    output.extend(Some(TokenTree::Punct(hash.clone())));
+                    index += 1;
+                }
+                [TokenTree::Group(g), ..] => {
+                    // Repetitions inside a nested `seq!` belong to that invocation.
+                    if is_nested_seq(&tokens[..index]) {
+                        output.extend(Some(TokenTree::Group(g.clone())));
+                    } else {
+                        let mut group = proc_macro2::Group::new(g.delimiter(), self.expand_repeat_impl(&g.stream(), matched, conditional)?);
+                        group.set_span(g.span());
+                        output.extend(Some(TokenTree::Group(group)));
+                    }
+                    index += 1;
+                }
+                [token, ..] => {
+                    output.extend(Some(token.clone()));
+                    index += 1;
+                }
+                [] => unreachable!(),
             }
         }
-        output
+        Ok(output)
     }
 }
 
@@ -186,19 +1123,22 @@ impl SeqInput {
 #[proc_macro]
 pub fn seq(input: TokenStream) -> TokenStream {
     let seq_input = parse_macro_input!(input as SeqInput);
-    eprintln!(">>> [content]: {}", seq_input.content);
     let mut ret = proc_macro2::TokenStream::new();
 
-    if let Some(output) = seq_input.expand_repeat() {
-        eprintln!("Cursor output: {}", output);
-        ret.extend(output);
-    } else {
-        eprintln!("Cursor output: None");
-        for i in seq_input.begin..seq_input.end {
-            ret.extend(seq_input.expand(&seq_input.content, i));
+    let expanded = match seq_input.expand_repeat() {
+        Ok(Some(output)) => Ok(output),
+        Ok(None) => {
+            seq_input.iterations()
+                .iter()
+                .map(|n| seq_input.expand(&seq_input.content, n))
+                .collect()
         }
+        Err(e) => Err(e),
+    };
+    match expanded {
+        Ok(output) => ret.extend(output),
+        Err(e) => return e.to_compile_error().into(),
     }
 
-    eprintln!(">>> [output]: {}", ret);
     ret.into()
 }
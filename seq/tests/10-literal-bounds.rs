@@ -0,0 +1,48 @@
+// Range bounds may be negative, written in hex, octal or binary, and carry a
+// type suffix. The literals substituted for the loop variable keep the radix
+// and suffix of the bounds, so `0x0u8..0x4u8` produces `0x0u8`, `0x1u8`, ...
+
+use seq::seq;
+
+fn main() {
+    let negative = seq!(N in -3..3 { [#(N,)*] });
+    assert_eq!(negative, [-3, -2, -1, 0, 1, 2]);
+
+    let suffixed = seq!(N in 0u8..4u8 { [#(N,)*] });
+    let _: [u8; 4] = suffixed;
+    assert_eq!(suffixed, [0, 1, 2, 3]);
+
+    // Only one bound needs the suffix.
+    let mixed = seq!(N in 250..=255u8 { [#(N,)*] });
+    assert_eq!(mixed, [250, 251, 252, 253, 254, 255]);
+
+    let hex = seq!(N in 0x0e..0x12 { [#(N,)*] });
+    assert_eq!(hex, [14, 15, 16, 17]);
+
+    let binary = seq!(N in 0b101..=0b111i16 { [#(N,)*] });
+    let _: [i16; 3] = binary;
+    assert_eq!(binary, [5, 6, 7]);
+
+    let signed = seq!(N in -0x2i8..0x2i8 { [#(N,)*] });
+    assert_eq!(signed, [-2i8, -1, 0, 1]);
+
+    // A negative value is substituted as a single operand, so a method call
+    // applies to the whole number rather than only to its digits.
+    let squares = seq!(N in -2i32..0i32 { [#(N.pow(2),)*] });
+    assert_eq!(squares, [4, 1]);
+
+    let abs = seq!(N in -2i32..=0 { [#(N.abs(),)*] });
+    assert_eq!(abs, [2, 1, 0]);
+
+    let cubes = seq!(N in 1i32..3 { [#(#{0 - N}.pow(3),)*] });
+    assert_eq!(cubes, [-1, -8]);
+
+    let listed = seq!(N in [-3i32, 2i32] { [#(N.abs(),)*] });
+    assert_eq!(listed, [3, 2]);
+
+    // Pasted identifiers always use the decimal value.
+    seq!(N in 0x8..0xa {
+        fn f~N() -> u32 { N }
+    });
+    assert_eq!(f8() + f9(), 17);
+}
@@ -0,0 +1,10 @@
+// A negative value can't be pasted into an identifier, since `f-1` is not an
+// identifier. This is reported at the loop variable after the `~`.
+
+use seq::seq;
+
+seq!(N in -1..1 {
+    fn f~N() {}
+});
+
+fn main() {}
@@ -0,0 +1,31 @@
+// Ranges can be walked with a stride, `0..64 step 8`, and in descending order
+// with `(0..8).rev()`. The two combine the same way `.rev().step_by(n)` does
+// on an iterator: the stride starts from the last value of the range.
+
+use seq::seq;
+
+seq!(N in 0..64 step 16 {
+    const REG~N: usize = N;
+});
+
+fn main() {
+    assert_eq!([REG0, REG16, REG32, REG48], [0, 16, 32, 48]);
+
+    let stepped = seq!(N in 1..=10 step 3 { [#(N,)*] });
+    assert_eq!(stepped, [1, 4, 7, 10]);
+
+    let reversed = seq!(N in (0..4).rev() { [#(N,)*] });
+    assert_eq!(reversed, [3, 2, 1, 0]);
+
+    let inclusive = seq!(N in (-2..=2).rev() { [#(N,)*] });
+    assert_eq!(inclusive, [2, 1, 0, -1, -2]);
+
+    let both = seq!(N in (0..8).rev() step 3 { [#(N,)*] });
+    assert_eq!(both, [7, 4, 1]);
+
+    let mut sum = 0;
+    seq!(N in (0u8..3u8).rev() {
+        sum += N;
+    });
+    assert_eq!(sum, 3);
+}
@@ -0,0 +1,9 @@
+// A step of zero would never reach the end of the range.
+
+use seq::seq;
+
+seq!(N in 0..4 step 0 {
+    const X~N: usize = N;
+});
+
+fn main() {}
@@ -0,0 +1,43 @@
+// Bounds can be computed from integer literals in parentheses, and can come
+// from the metavariables of an enclosing `macro_rules!` macro. An `$n:expr`
+// fragment reaches `seq!` wrapped in an invisible group, which is evaluated
+// like a parenthesized expression.
+
+use seq::seq;
+
+macro_rules! registers {
+    ($count:expr, $stride:literal) => {
+        seq!(N in 0..$count step $stride {
+            const REGS: [usize; $count / $stride] = [#(N,)*];
+        });
+    };
+}
+
+registers!(4 * 4, 4);
+
+macro_rules! sum {
+    ($lo:expr, $hi:expr) => {
+        seq!(N in $lo..=$hi { 0 #(+ N)* })
+    };
+}
+
+fn main() {
+    assert_eq!(REGS, [0, 4, 8, 12]);
+
+    let doubled = seq!(N in 0..(2 * 2) { [#(N,)*] });
+    assert_eq!(doubled, [0, 1, 2, 3]);
+
+    let shifted = seq!(N in (1 << 3)..((1 << 3) + 2) { [#(N,)*] });
+    assert_eq!(shifted, [8, 9]);
+
+    let negative = seq!(N in -(1 + 1)..(7 % 5) { [#(N,)*] });
+    assert_eq!(negative, [-2, -1, 0, 1]);
+
+    // Suffixes and radix carry through the arithmetic.
+    let bytes = seq!(N in 0x10u8..(0x10 + 3) { [#(N,)*] });
+    let _: [u8; 3] = bytes;
+    assert_eq!(bytes, [0x10, 0x11, 0x12]);
+
+    assert_eq!(sum!(1, 2 + 2), 10);
+    assert_eq!(sum!(-1, 1), 0);
+}
@@ -0,0 +1,12 @@
+// A `const` item has no value yet while `seq!` is expanding, so it can't be
+// used as a bound. The error points at the path.
+
+use seq::seq;
+
+const LEN: usize = 4;
+
+seq!(N in 0..(LEN + 1) {
+    const X~N: usize = N;
+});
+
+fn main() {}
@@ -0,0 +1,64 @@
+// Pasting joins any chain of identifiers separated by `~`, so the loop
+// variable can sit in the middle of a name, `Reg~N~Mask`, and the result can
+// be used anywhere an identifier can: in paths, after the `'` of a lifetime
+// and so on. `~{N:02}` zero-pads the number. Inside string literals, `~N` and
+// `~{N:02}` are replaced by the number as well.
+//
+// Outside of the braces, a `:` after a pasted name is left alone, so field
+// inits like `a0~N: 05` keep their value, and so does `"~N:05"` in a string.
+
+use seq::seq;
+
+mod regs {
+    seq::seq!(N in 0..12 step 3 {
+        pub struct Reg~{N:02}~Mask;
+
+        impl Reg~{N:02}~Mask {
+            pub const NAME: &'static str = "Reg~{N:02}Mask";
+            pub const BIT: u32 = 1 << N;
+        }
+    });
+}
+
+struct Borrowed<'a>(&'a str);
+
+seq!(N in 1..3 {
+    fn borrow~N<'a~N>(s: &'a~N str) -> Borrowed<'a~N> {
+        Borrowed(s)
+    }
+});
+
+seq!(N in 0..3 {
+    #[derive(Default)]
+    struct Fields {
+        #(field_~N: u8,)*
+    }
+    const NAMES: [&str; 3] = [#("field_~N",)*];
+});
+
+pub struct Pair {
+    a00: u8,
+    a01: u8,
+}
+
+fn main() {
+    assert_eq!(regs::Reg03Mask::NAME, "Reg03Mask");
+    assert_eq!(regs::Reg09Mask::BIT, 1 << 9);
+    let _ = (regs::Reg00Mask, regs::Reg06Mask);
+
+    assert_eq!(borrow1("x").0, borrow2("x").0);
+
+    let fields = seq!(N in 0..2 { Fields { #(field_~N: 01,)* ..Default::default() } });
+    assert_eq!(fields.field_0 + fields.field_1 + fields.field_2, 2);
+    assert_eq!(NAMES, ["field_0", "field_1", "field_2"]);
+
+    let pair = seq!(N in 0..1 { Pair { a0~N: 05, a01: 1 } });
+    assert_eq!((pair.a00, pair.a01), (5, 1));
+
+    // A `~` not followed by the loop variable is left as it was.
+    let s = seq!(N in 0..1 { "~Name ~N" });
+    assert_eq!(s, "~Name 0");
+
+    let s = seq!(N in 3..4 { "~N:05 ~{N:03}" });
+    assert_eq!(s, "3:05 003");
+}
@@ -0,0 +1,35 @@
+// A `seq!` inside the body of another one is expanded once per value of the
+// outer variable. Its range can depend on the outer variable, and identifiers
+// can paste both variables together: the outer expansion leaves `~J` in place
+// for the nested invocation to fill in.
+//
+// Repetitions, `#(...)*`, inside the nested invocation are repeated by the
+// nested invocation only. A nested variable with the same name as the outer
+// one shadows it in the nested body.
+
+use seq::seq;
+
+seq!(I in 0..3 {
+    seq!(J in 0..3 {
+        const M~I~_~J: usize = I * 10 + J;
+    });
+});
+
+seq!(I in 0..4 {
+    const TRIANGLE~I: usize = seq!(J in 0..I { 0 #(+ J + 1)* });
+});
+
+fn main() {
+    assert_eq!([M0_0, M1_2, M2_1, M2_2], [0, 12, 21, 22]);
+    assert_eq!([TRIANGLE0, TRIANGLE1, TRIANGLE2, TRIANGLE3], [0, 1, 3, 6]);
+
+    let pairs = seq!(I in 0..2 {
+        [#(seq!(J in 0..2 { [#((I, J),)*] }),)*]
+    });
+    assert_eq!(pairs, [[(0, 0), (0, 1)], [(1, 0), (1, 1)]]);
+
+    let shadowed = seq!(N in 1..3 {
+        [#(seq!(N in 0..N { 0 #(+ N)* }),)*]
+    });
+    assert_eq!(shadowed, [0, 1]);
+}
@@ -0,0 +1,44 @@
+// Several variables can be bound in one invocation. `(I, J) in A x B` visits
+// every combination of values, with the last variable varying fastest, which
+// lays out a 2-D table row by row. `N in A, M in B zip` walks the ranges side
+// by side instead, so they need to have the same number of values.
+//
+// A repetition `#(...)*` runs once per iteration, i.e. once per combination.
+
+use seq::seq;
+
+seq!((ROW, COL) in (0..3) x (0..4) {
+    const TABLE: [(usize, usize); 12] = [#((ROW, COL),)*];
+});
+
+seq!(N in 0..4, BASE in (0x1000..0x1100).rev() step 64 zip {
+    #(
+        const REG~N: usize = BASE;
+    )*
+});
+
+fn main() {
+    assert_eq!(TABLE[0], (0, 0));
+    assert_eq!(TABLE[5], (1, 1));
+    assert_eq!(TABLE[11], (2, 3));
+
+    let flat = seq!((I, J) in 0..2 x (0..3).rev() { [#((I, J),)*] });
+    assert_eq!(flat, [(0, 2), (0, 1), (0, 0), (1, 2), (1, 1), (1, 0)]);
+
+    let cells = seq!((I, J) in (0..2) x (0..2) { [#(cell~I~_~J(),)*] });
+    assert_eq!(cells, ["0,0", "0,1", "1,0", "1,1"]);
+
+    let cube = seq!((I, J, K) in (0..2) x (0..2) x (0..2) { 0 #(+ I * 4 + J * 2 + K)* });
+    assert_eq!(cube, 28);
+
+    assert_eq!([REG0, REG1, REG2, REG3], [0x10ff, 0x10bf, 0x107f, 0x103f]);
+
+    let pairs = seq!(N in 0..3, M in 10..13 zip { [#((N, M, "~N-~M"),)*] });
+    assert_eq!(pairs, [(0, 10, "0-10"), (1, 11, "1-11"), (2, 12, "2-12")]);
+}
+
+seq!((I, J) in (0..2) x (0..2) {
+    fn cell~I~_~J() -> &'static str {
+        "~I,~J"
+    }
+});
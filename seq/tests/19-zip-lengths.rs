@@ -0,0 +1,13 @@
+// Zipped ranges have to produce the same number of values.
+
+use seq::seq;
+
+seq!(N in 0..4, M in 0..8 step 2 zip {
+    const A~N: usize = M;
+});
+
+seq!(N in 0..4, M in 0..3 zip {
+    const B~N: usize = M;
+});
+
+fn main() {}
@@ -0,0 +1,47 @@
+// A repetition can put a separator between its iterations, the same way as in
+// `macro_rules!`: `#(...),*` for a comma list without a trailing comma,
+// `#(...)+*` for a sum and so on. The separator is any single token or
+// multi-character operator between the `)` and the `*`.
+
+use seq::seq;
+
+seq!(N in 0..4 {
+    fn sum(a: [u32; 4]) -> u32 {
+        #(a[N])+*
+    }
+
+    fn call(f: fn(u8, u8, u8, u8) -> u8) -> u8 {
+        f(#(N),*)
+    }
+
+    fn all(bits: [bool; 4]) -> bool {
+        #(bits[N])&&*
+    }
+});
+
+fn add(a: u8, b: u8, c: u8, d: u8) -> u8 {
+    a + b + c + d
+}
+
+fn main() {
+    assert_eq!(sum([1, 2, 3, 4]), 10);
+    assert_eq!(call(add), 6);
+    assert!(all([true; 4]));
+    assert!(!all([true, false, true, true]));
+
+    // With whitespace between the separator and the `*`.
+    let tuple = seq!(N in 0..3 { (#(N) , *) });
+    assert_eq!(tuple, (0, 1, 2));
+
+    // An empty range produces no separators either.
+    let empty: [u8; 0] = seq!(N in 0..0 { [#(N),*] });
+    assert_eq!(empty, []);
+
+    let matched = seq!(N in 0..3 {
+        match 1 {
+            #(N => "~N"),*,
+            _ => "",
+        }
+    });
+    assert_eq!(matched, "1");
+}
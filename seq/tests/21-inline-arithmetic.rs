@@ -0,0 +1,51 @@
+// `#{...}` evaluates an expression over the loop variables and integer
+// literals while expanding, and emits the result as a literal. This works in
+// places where Rust doesn't accept an expression, like patterns, and it can be
+// pasted into identifiers with `~#{...}` like a loop variable, zero-padded
+// with `~{#{N + 1}:02}`.
+
+use seq::seq;
+
+seq!(N in 0..4 {
+    struct Reg~N {
+        bits: [u8; #{1 << N}],
+    }
+
+    fn next~N() -> usize {
+        let reg = Reg~N { bits: [0; #{1 << N}] };
+        reg.bits.len() + next_~{#{N + 1}:02}()
+    }
+
+    fn next_~{#{N + 1}:02}() -> usize {
+        #{N * 4}
+    }
+});
+
+seq!(N in 0..3 {
+    fn classify(x: (u8, u8)) -> &'static str {
+        match x {
+            #((N, #{N + 1}) => "step ~N",)*
+            _ => "other",
+        }
+    }
+});
+
+fn main() {
+    assert_eq!(next0(), 1);
+    assert_eq!(next3(), 8 + 12);
+    assert_eq!(next_02(), 4);
+
+    assert_eq!(classify((1, 2)), "step 1");
+    assert_eq!(classify((1, 1)), "other");
+
+    // The result keeps the radix and suffix of the range bounds.
+    let masks = seq!(N in 0u8..4 { [#(#{0xf0 >> N},)*] });
+    let _: [u8; 4] = masks;
+    assert_eq!(masks, [0xf0, 0x78, 0x3c, 0x1e]);
+
+    // Nested invocations evaluate the parts that depend on their variables.
+    let table = seq!(I in 1..3 {
+        [#(seq!(J in 0..2 { [#(#{I * 10 + J},)*] }),)*]
+    });
+    assert_eq!(table, [[10, 11], [20, 21]]);
+}
@@ -0,0 +1,12 @@
+// `#{...}` is evaluated by the macro, so it can only use loop variables and
+// literals, not other items in scope.
+
+use seq::seq;
+
+const SCALE: usize = 4;
+
+seq!(N in 0..2 {
+    const X~N: [u8; 1] = [0; #{N * SCALE}];
+});
+
+fn main() {}
@@ -0,0 +1,63 @@
+// Besides integer ranges, a variable can walk a list of types or literals:
+// `T in [u8, u16, u32]`. Each item is substituted as written. Pasting uses the
+// item's name, and `T_index` holds the position of the item in the list, for
+// when a number is needed instead. Inside a string, any item can be pasted
+// and is written the way it would be in code, `"Vec<u8>"` or `"-20"`.
+//
+// Lists combine with the other features like ranges do: steps, zipping,
+// products and separators.
+
+use seq::seq;
+
+trait Width {
+    const BITS: u32;
+    const NAME: &'static str;
+    const INDEX: usize;
+}
+
+seq!(T in [u8, u16, u32, u64, Vec<u8>, &'static [std::fmt::Result; 2]] {
+    #(
+        impl Width for T {
+            const BITS: u32 = T_index as u32 * 8;
+            const NAME: &'static str = "~T";
+            const INDEX: usize = T_index;
+        }
+    )*
+});
+
+seq!(T in [u8, i8] {
+    fn max_~T() -> T {
+        T::MAX
+    }
+});
+
+seq!(C in ['a', 'b', 'c'] {
+    const LETTERS: [char; 3] = [#(C),*];
+    #(fn position_~C() -> usize { C_index })*
+});
+
+fn main() {
+    assert_eq!(<u32 as Width>::BITS, 16);
+    assert_eq!(<u64 as Width>::NAME, "u64");
+    assert_eq!(<Vec<u8> as Width>::INDEX, 4);
+    assert_eq!(<Vec<u8> as Width>::NAME, "Vec<u8>");
+    assert_eq!(<&[std::fmt::Result; 2] as Width>::NAME, "&'static [std::fmt::Result; 2]");
+
+    assert_eq!(max_u8(), 255);
+    assert_eq!(max_i8(), 127);
+
+    assert_eq!(LETTERS, ['a', 'b', 'c']);
+    assert_eq!(position_c(), 2);
+
+    let every_other = seq!(X in [10, -20, 30, -40] step 2 { [#(X),*] });
+    assert_eq!(every_other, [10, 30]);
+
+    let negative = seq!(X in [-20, 30] { [#("~X"),*] });
+    assert_eq!(negative, ["-20", "30"]);
+
+    let named = seq!(N in 0..3, S in ["zero", "one", "two"] zip { [#((N, S, "~S=~N")),*] });
+    assert_eq!(named[2], (2, "two", "two=2"));
+
+    let product = seq!((T, I) in [u8, u16] x (0..2) { [#(core::mem::size_of::<T>() * I + T_index),*] });
+    assert_eq!(product, [0, 1, 1, 3]);
+}
@@ -0,0 +1,15 @@
+// Only items with a name, or literals that would fit in an identifier, can be
+// pasted. Characters are checked against the rules for identifiers, which
+// don't allow every alphanumeric character.
+
+use seq::seq;
+
+seq!(T in [u8, Vec<u8>] {
+    fn len_~T() {}
+});
+
+seq!(C in ['é', '²'] {
+    fn f~C() {}
+});
+
+fn main() {}
@@ -0,0 +1,46 @@
+// `#if (condition) { ... }` keeps its contents only for the iterations where
+// the condition holds, and `#else { ... }` or `#else #if ...` can follow it.
+// Conditions compare integer expressions over the loop variables with `==`,
+// `<` and so on, combined with `&&`, `||` and `!`.
+
+use seq::seq;
+
+seq!(N in 0..6 {
+    #(
+        #if (N == 0) {
+            const FIRST: usize = N;
+        } #else #if (N == 5) {
+            const LAST: usize = N;
+        } #else {
+            const MIDDLE~N: usize = N;
+        }
+    )*
+
+    fn evens() -> [usize; 3] {
+        [#(#if (N % 2 == 0) { N, })*]
+    }
+
+    fn describe(n: usize) -> &'static str {
+        match n {
+            #(N => #if (N < 2 || N > 3 && !(N == 5)) { "outer" } #else { "inner" },)*
+            _ => "none",
+        }
+    }
+});
+
+fn main() {
+    assert_eq!(FIRST, 0);
+    assert_eq!(LAST, 5);
+    assert_eq!(MIDDLE1 + MIDDLE2 + MIDDLE3 + MIDDLE4, 10);
+    assert_eq!(evens(), [0, 2, 4]);
+    assert_eq!(describe(1), "outer");
+    assert_eq!(describe(3), "inner");
+    assert_eq!(describe(4), "outer");
+    assert_eq!(describe(5), "inner");
+
+    // Nested invocations decide conditions on their own variables.
+    let diagonal = seq!(I in 0..3 {
+        [#(seq!(J in 0..3 { [#(#if (I == J) { 1 } #else { 0 },)*] }),)*]
+    });
+    assert_eq!(diagonal, [[1, 0, 0], [0, 1, 0], [0, 0, 1]]);
+}
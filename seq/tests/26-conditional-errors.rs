@@ -0,0 +1,32 @@
+// Conditions need parentheses and a comparison, and `#else` needs an `#if`
+// in front of it. In a body with a repeated section, conditionals only make
+// sense inside the section, where the variable has a value.
+
+use seq::seq;
+
+seq!(N in 0..2 {
+    #if N == 0 {
+        const A: usize = 0;
+    }
+});
+
+seq!(N in 0..2 {
+    #if (N + 1) {
+        const B~N: usize = 0;
+    }
+});
+
+seq!(N in 0..2 {
+    #else {
+        const C~N: usize = 0;
+    }
+});
+
+seq!(N in 0..2 {
+    #if (N == 0) {
+        const D: usize = 0;
+    }
+    const E: [usize; 2] = [#(N,)*];
+});
+
+fn main() {}
@@ -0,0 +1,36 @@
+// Mistakes in the header are reported at the offending bound rather than by
+// panicking. A reversed range asks for `.rev()`, bounds have to fit in an
+// `i128`, and a negative bound can't have an unsigned suffix. Empty ranges are
+// allowed and expand to nothing.
+
+use seq::seq;
+
+seq!(N in 0..0 {
+    compile_error!("unreachable");
+});
+
+seq!(N in 5..=4 {
+    compile_error!("unreachable");
+});
+
+seq!(N in 5..2 {
+    const A~N: usize = N;
+});
+
+seq!(N in 0..(170141183460469231731687303715884105727 + 1) {
+    const B~N: usize = N;
+});
+
+seq!(N in 0..999999999999999999999999999999999999999999 {
+    const C~N: usize = N;
+});
+
+seq!(N in -2u8..0 {
+    const D~N: u8 = N;
+});
+
+seq!(N in -2..=1u16 {
+    const E~N: u16 = N;
+});
+
+fn main() {}
@@ -0,0 +1,13 @@
+// A repeated section has to end in `*`, and sections can't be nested.
+
+use seq::seq;
+
+seq!(N in 0..4 {
+    const A: [usize; 4] = [#(N,)+];
+});
+
+seq!(N in 0..4 {
+    const B: [[usize; 4]; 4] = [#([#(N,)*],)*];
+});
+
+fn main() {}
@@ -0,0 +1,42 @@
+// An `$e:expr` fragment from `macro_rules!` is wrapped in an invisible group
+// that makes it behave like a single operand. The group has to survive a
+// repeated section, so that `#{$e * N}` with `$e` = `1 + 1` evaluates to
+// `2 * N` rather than `1 + 1 * N`.
+
+use seq::seq;
+
+macro_rules! scaled {
+    ($e:expr) => {
+        seq!(N in 0..4 {
+            [#(#{$e * N},)*]
+        })
+    };
+}
+
+macro_rules! tables {
+    ($($name:ident = $e:expr;)*) => {
+        seq!(N in 1..3 {
+            $(const $name: [i32; 2] = [#(#{-$e * N},)*];)*
+        });
+    };
+}
+
+tables! {
+    DOUBLED = 1 + 1;
+    NEGATED = 3 - 4;
+}
+
+macro_rules! first_of {
+    ($e:expr) => {
+        seq!(N in 0..4 {
+            [#(#if (N == $e % 3) { N, })*]
+        })
+    };
+}
+
+fn main() {
+    assert_eq!(scaled!(1 + 1), [0, 2, 4, 6]);
+    assert_eq!(DOUBLED, [-2, -4]);
+    assert_eq!(NEGATED, [1, 2]);
+    assert_eq!(first_of!(2 + 2), [1]);
+}
@@ -0,0 +1,21 @@
+// Errors in code inside a repeated section point at that code, including
+// inside nested groups, and at the loop variable where it was substituted.
+
+use seq::seq;
+
+seq!(N in 0..2 {
+    #(
+        fn f~N() -> u8 {
+            {
+                let x: u8 = "not a number";
+                x + N
+            }
+        }
+    )*
+
+    fn g() -> [u8; 2] {
+        [#(N as u16,)*]
+    }
+});
+
+fn main() {}
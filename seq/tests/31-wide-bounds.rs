@@ -0,0 +1,26 @@
+// Bounds are evaluated as `i128`, so ranges near the ends of `u64`, `i64` and
+// `i128` work, as long as the range itself stays within the iteration limit.
+
+use seq::seq;
+
+fn main() {
+    let top = seq!(N in 0xffff_ffff_ffff_fffdu64..=0xffff_ffff_ffff_ffffu64 { [#(N,)*] });
+    assert_eq!(top, [u64::MAX - 2, u64::MAX - 1, u64::MAX]);
+
+    let bottom = seq!(N in -9223372036854775808i64..-9223372036854775806i64 { [#(N,)*] });
+    assert_eq!(bottom, [i64::MIN, i64::MIN + 1]);
+
+    let wide = seq!(N in (170141183460469231731687303715884105725i128..=170141183460469231731687303715884105727).rev() {
+        [#(N,)*]
+    });
+    assert_eq!(wide, [i128::MAX, i128::MAX - 1, i128::MAX - 2]);
+
+    let ends = seq!(N in (-170141183460469231731687303715884105728i128..=170141183460469231731687303715884105727).rev() step 170141183460469231731687303715884105727 {
+        [#(N,)*]
+    });
+    assert_eq!(ends, [i128::MAX, 0, i128::MIN + 1]);
+
+    // The step keeps a huge span within the limit.
+    let sparse = seq!(N in 0u128..(1 << 100) step 0x4000_0000_0000_0000_0000_0000_0 { [#(N,)*] });
+    assert_eq!(sparse, [0, 1 << 98, 2 << 98, 3 << 98]);
+}
@@ -0,0 +1,16 @@
+// A range that would produce more iterations than the limit is an error,
+// pointing at the range. The limit defaults to 65536 and can be changed with
+// the SEQ_MAX_ITERATIONS environment variable. For a product of ranges, the
+// total number of combinations counts.
+
+use seq::seq;
+
+seq!(N in 0..1000000 {
+    const A~N: usize = N;
+});
+
+seq!((I, J) in (0..300) x (0..300) {
+    const B~I~_~J: usize = I;
+});
+
+fn main() {}
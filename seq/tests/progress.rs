@@ -1,13 +1,36 @@
 #[test]
 fn tests() {
     let t = trybuild::TestCases::new();
-    //t.pass("tests/01-parse-header.rs");
-    //t.pass("tests/02-parse-body.rs");
-    //t.compile_fail("tests/03-expand-four-errors.rs");
-    //t.pass("tests/04-paste-ident.rs");
-    //t.pass("tests/05-repeat-section.rs");
-    //t.pass("tests/06-init-array.rs");
-    //t.pass("tests/07-inclusive-range.rs");
-    //t.compile_fail("tests/08-ident-span.rs");
-    //t.pass("tests/09-interaction-with-macrorules.rs");
+    t.pass("tests/01-parse-header.rs");
+    t.pass("tests/02-parse-body.rs");
+    t.compile_fail("tests/03-expand-four-errors.rs");
+    t.pass("tests/04-paste-ident.rs");
+    t.pass("tests/05-repeat-section.rs");
+    t.pass("tests/06-init-array.rs");
+    t.pass("tests/07-inclusive-range.rs");
+    t.compile_fail("tests/08-ident-span.rs");
+    t.pass("tests/09-interaction-with-macrorules.rs");
+    t.pass("tests/10-literal-bounds.rs");
+    t.compile_fail("tests/11-negative-paste.rs");
+    t.pass("tests/12-step-rev.rs");
+    t.compile_fail("tests/13-zero-step.rs");
+    t.pass("tests/14-expression-bounds.rs");
+    t.compile_fail("tests/15-const-item-bound.rs");
+    t.pass("tests/16-paste-forms.rs");
+    t.pass("tests/17-nested.rs");
+    t.pass("tests/18-multiple-variables.rs");
+    t.compile_fail("tests/19-zip-lengths.rs");
+    t.pass("tests/20-separators.rs");
+    t.pass("tests/21-inline-arithmetic.rs");
+    t.compile_fail("tests/22-inline-not-constant.rs");
+    t.pass("tests/23-lists.rs");
+    t.compile_fail("tests/24-list-paste.rs");
+    t.pass("tests/25-conditionals.rs");
+    t.compile_fail("tests/26-conditional-errors.rs");
+    t.compile_fail("tests/27-range-errors.rs");
+    t.compile_fail("tests/28-section-errors.rs");
+    t.pass("tests/29-macro-rules-groups.rs");
+    t.compile_fail("tests/30-section-spans.rs");
+    t.pass("tests/31-wide-bounds.rs");
+    t.compile_fail("tests/32-iteration-limit.rs");
 }